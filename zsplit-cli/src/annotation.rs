@@ -0,0 +1,87 @@
+use crate::source::{Source, SourcePosition};
+use std::sync::Arc;
+use zsplit::transform::{Record, Transform};
+
+#[cfg(test)]
+#[path = "./annotation_test.rs"]
+mod annotation_test;
+
+/// The CLI names of the fields, which annotate a record.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Field {
+    Source,
+    Number,
+    Offset,
+}
+
+/// Prefixes every record with the fields of its origin, each followed by the separator.
+#[derive(Debug, Clone)]
+pub(crate) struct Annotation {
+    fields: Vec<Field>,
+    separator: String,
+    /// The count of records, which were skipped before splitting and precede the numbers.
+    skipped: u64,
+    sources: Arc<[String]>,
+    /// The source, whose records are split, and its start.
+    position: Arc<SourcePosition>,
+}
+
+impl Annotation {
+    pub(crate) fn new(
+        fields: Vec<Field>,
+        separator: String,
+        skipped: u64,
+        sources: &[Source],
+        position: Arc<SourcePosition>,
+    ) -> Self {
+        Self {
+            fields,
+            separator,
+            skipped,
+            sources: sources.iter().map(name).collect(),
+            position,
+        }
+    }
+
+    fn source(&self) -> &str {
+        self.sources
+            .get(self.position.index())
+            .map_or("", String::as_str)
+    }
+
+    /// The number of the `record` in its source.
+    fn number(&self, record: &Record<'_>) -> u64 {
+        record
+            .number()
+            .saturating_add(self.skipped)
+            .saturating_sub(self.position.record())
+    }
+
+    /// The byte offset of the `record` in its source.
+    fn offset(&self, record: &Record<'_>) -> u64 {
+        record.offset().saturating_sub(self.position.offset())
+    }
+}
+
+impl Transform for Annotation {
+    fn transform(&self, record: &Record<'_>, output: &mut String) {
+        for field in &self.fields {
+            match field {
+                Field::Source => output.push_str(self.source()),
+                Field::Number => output.push_str(&self.number(record).to_string()),
+                Field::Offset => output.push_str(&self.offset(record).to_string()),
+            }
+            output.push_str(&self.separator);
+        }
+        output.push_str(record.line());
+    }
+}
+
+/// The name of the `source` in an annotation.
+fn name(source: &Source) -> String {
+    match source {
+        Source::PathBuf(path) => path.display().to_string(),
+        Source::Glob(pattern) => pattern.clone(),
+        Source::StdIn => "-".to_owned(),
+    }
+}
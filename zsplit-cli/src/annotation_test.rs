@@ -0,0 +1,62 @@
+use super::*;
+use std::path::PathBuf;
+
+fn annotated(annotation: &Annotation, record: &Record<'_>) -> String {
+    let mut output = String::new();
+    annotation.transform(record, &mut output);
+    output
+}
+
+#[test]
+fn annotate_number_and_offset() {
+    let annotation = Annotation::new(
+        vec![Field::Number, Field::Offset],
+        "\t".to_owned(),
+        10,
+        &[Source::StdIn],
+        Arc::default(),
+    );
+
+    assert_eq!(
+        annotated(&annotation, &Record::new("record", 2, 14)),
+        "12\t14\trecord"
+    );
+}
+
+#[test]
+fn annotate_current_source() {
+    let position = Arc::new(SourcePosition::default());
+    let annotation = Annotation::new(
+        vec![Field::Source],
+        ":".to_owned(),
+        0,
+        &[
+            Source::PathBuf(PathBuf::from("a.txt")),
+            Source::PathBuf(PathBuf::from("b.txt")),
+        ],
+        Arc::clone(&position),
+    );
+    let record = Record::new("record", 0, 0);
+
+    assert_eq!(annotated(&annotation, &record), "a.txt:record");
+    position.enter(1, 0, 0);
+    assert_eq!(annotated(&annotation, &record), "b.txt:record");
+}
+
+#[test]
+fn annotate_position_in_source() {
+    let position = Arc::new(SourcePosition::default());
+    let annotation = Annotation::new(
+        vec![Field::Number, Field::Offset],
+        ":".to_owned(),
+        1,
+        &[Source::StdIn, Source::StdIn],
+        Arc::clone(&position),
+    );
+    position.enter(1, 2, 6);
+
+    assert_eq!(
+        annotated(&annotation, &Record::new("record", 3, 10)),
+        "2:4:record"
+    );
+}
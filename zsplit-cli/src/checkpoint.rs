@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use zsplit::checkpoint::Checkpoint;
+use zsplit::compression::Compression;
+
+#[cfg(test)]
+#[path = "./checkpoint_test.rs"]
+mod checkpoint_test;
+
+/// The progress of a split saved into the state file of `--checkpoint`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub(crate) struct State {
+    pub(crate) source: PathBuf,
+    pub(crate) line_factor: usize,
+    pub(crate) distributions: Vec<usize>,
+    pub(crate) record: u64,
+    pub(crate) offset: u64,
+    pub(crate) destinations: Vec<DestinationState>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub(crate) struct DestinationState {
+    pub(crate) path: PathBuf,
+    pub(crate) bytes: u64,
+}
+
+impl State {
+    pub(crate) fn new(
+        source: &Path,
+        line_factor: usize,
+        distributions: &[usize],
+        paths: &[PathBuf],
+        checkpoint: &Checkpoint,
+    ) -> Self {
+        Self {
+            source: source.to_owned(),
+            line_factor,
+            distributions: distributions.to_vec(),
+            record: checkpoint.record,
+            offset: checkpoint.offset,
+            destinations: paths
+                .iter()
+                .zip(&checkpoint.destinations)
+                .map(|(path, bytes)| DestinationState {
+                    path: path.clone(),
+                    bytes: *bytes,
+                })
+                .collect(),
+        }
+    }
+
+    /// Reads the state file at `path`, if it exists.
+    pub(crate) fn load(path: &Path) -> io::Result<Option<Self>> {
+        match fs::read(path) {
+            Ok(content) => Ok(Some(serde_json::from_slice(&content)?)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Replaces the state file at `path` by renaming a temporary file, so it is never truncated.
+    ///
+    /// The destinations and the temporary file are synced before, so the state never refers to
+    /// bytes, which could be lost on a crash.
+    pub(crate) fn save(&self, path: &Path) -> io::Result<()> {
+        self.destinations
+            .iter()
+            .try_for_each(DestinationState::sync)?;
+
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+
+        let mut file = File::create(&temporary)?;
+        file.write_all(&serde_json::to_vec_pretty(self)?)?;
+        file.sync_data()?;
+        fs::rename(&temporary, path)
+    }
+
+    /// Whether the state belongs to a split of `source` into `paths`, which are assigned the same
+    /// records.
+    pub(crate) fn matches(
+        &self,
+        source: &Path,
+        line_factor: usize,
+        distributions: &[usize],
+        paths: &[PathBuf],
+    ) -> bool {
+        self.source == source
+            && self.line_factor == line_factor
+            && self.distributions == distributions
+            && self
+                .destinations
+                .iter()
+                .map(|destination| &destination.path)
+                .eq(paths)
+    }
+
+    pub(crate) fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(
+            self.record,
+            self.offset,
+            self.destinations
+                .iter()
+                .map(|destination| destination.bytes)
+                .collect(),
+        )
+    }
+
+    /// Truncates the destinations to the bytes written up to the checkpoint.
+    pub(crate) fn truncate_destinations(&self) -> io::Result<()> {
+        self.destinations
+            .iter()
+            .try_for_each(DestinationState::truncate)
+    }
+}
+
+impl DestinationState {
+    fn sync(&self) -> io::Result<()> {
+        OpenOptions::new().write(true).open(&self.path)?.sync_data()
+    }
+
+    fn truncate(&self) -> io::Result<()> {
+        let file = OpenOptions::new().write(true).open(&self.path)?;
+
+        if file.metadata()?.len() < self.bytes {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "The file `{}` is shorter than at the checkpoint",
+                    self.path.display()
+                ),
+            ));
+        }
+        file.set_len(self.bytes)
+    }
+}
+
+/// Opens the uncompressed `source` at `offset`.
+pub(crate) fn open_source(source: &Path, offset: u64) -> io::Result<BufReader<File>> {
+    let mut reader = BufReader::new(File::open(source)?);
+
+    if Compression::from_magic_bytes(&mut reader)? != Compression::None {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "A compressed source can't be resumed",
+        ));
+    }
+
+    reader.seek(SeekFrom::Start(offset))?;
+    Ok(reader)
+}
@@ -0,0 +1,91 @@
+use super::*;
+use assert_fs::TempDir;
+
+fn state(dir: &Path) -> State {
+    State::new(
+        Path::new("source.txt"),
+        2,
+        &[1, 1],
+        &[dir.join("a"), dir.join("b")],
+        &Checkpoint::new(3, 6, vec![4, 2]),
+    )
+}
+
+#[test]
+fn save_and_load() {
+    let dir = TempDir::new().unwrap();
+    let state_file = dir.path().join("state.json");
+    let state = state(dir.path());
+    fs::write(dir.join("a"), "0\n2\n").unwrap();
+    fs::write(dir.join("b"), "1\n").unwrap();
+
+    state.save(&state_file).unwrap();
+
+    assert_eq!(State::load(&state_file).unwrap(), Some(state));
+    assert!(!dir.path().join("state.json.tmp").exists());
+}
+
+#[test]
+fn save_with_missing_destination() {
+    let dir = TempDir::new().unwrap();
+    let state_file = dir.path().join("state.json");
+
+    state(dir.path()).save(&state_file).unwrap_err();
+
+    assert!(!state_file.exists());
+}
+
+#[test]
+fn load_missing() {
+    let dir = TempDir::new().unwrap();
+
+    assert_eq!(State::load(&dir.path().join("state.json")).unwrap(), None);
+}
+
+#[test]
+fn checkpoint_of_state() {
+    let dir = TempDir::new().unwrap();
+    let state = state(dir.path());
+    let paths = [dir.join("a"), dir.join("b")];
+
+    assert_eq!(state.checkpoint(), Checkpoint::new(3, 6, vec![4, 2]));
+    assert!(state.matches(Path::new("source.txt"), 2, &[1, 1], &paths));
+    assert!(!state.matches(Path::new("source.txt"), 2, &[1, 1], &paths[..1]));
+    assert!(!state.matches(Path::new("source.txt"), 1, &[1, 1], &paths));
+    assert!(!state.matches(Path::new("source.txt"), 2, &[2, 1], &paths));
+}
+
+#[test]
+fn truncate_to_checkpoint() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.join("a"), "0\n2\n4\n").unwrap();
+    fs::write(dir.join("b"), "1\n").unwrap();
+
+    state(dir.path()).truncate_destinations().unwrap();
+
+    assert_eq!(fs::read_to_string(dir.join("a")).unwrap(), "0\n2\n");
+}
+
+#[test]
+fn truncate_shorter_destination() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.join("a"), "0\n2\n").unwrap();
+    fs::write(dir.join("b"), "").unwrap();
+
+    state(dir.path()).truncate_destinations().unwrap_err();
+}
+
+#[test]
+fn open_source_at_offset() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.join("source.txt"), "0\n1\n2\n").unwrap();
+
+    let mut source = String::new();
+    io::Read::read_to_string(
+        &mut open_source(&dir.join("source.txt"), 4).unwrap(),
+        &mut source,
+    )
+    .unwrap();
+
+    assert_eq!(source, "2\n");
+}
@@ -1,54 +1,408 @@
-use crate::source::Source;
+use crate::annotation::{Annotation, Field};
+use crate::checkpoint::State;
+use crate::dedup::Deduplication;
+use crate::manifest::{Checksummed, DestinationEntry, Manifest, SourceEntry};
+use crate::naming::{self, KeyTemplate, Template};
+use crate::sink::DestinationSink;
+use crate::source::{Source, SourceChain, SourcePosition};
 use bool_ext::BoolExt;
 use clap::{Parser, ValueHint};
 use error_stack::ResultExt;
-use std::io;
-use std::num::NonZeroUsize;
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::io::BufRead;
+use std::num::{NonZeroU32, NonZeroU64, NonZeroUsize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use zsplit::compression::Compression;
+use zsplit::dedup::DuplicateFilter;
+use zsplit::destination::Finish;
+use zsplit::file::{FileOptions, WritePolicy};
+use zsplit::flush::FlushPolicy;
+use zsplit::limit::{LimitedRecords, OversizedRecords};
+use zsplit::policy::ErrorPolicy;
+use zsplit::pool::FilePool;
+use zsplit::report::SplitReport;
+use zsplit::rotation::Rotation;
+use zsplit::select::{RecordRange, SelectedRecords};
 use zsplit::Destination;
 
 #[cfg(test)]
 #[path = "./cli_test.rs"]
 pub(crate) mod cli_test;
 
+const MANIFEST_FILE_NAME: &str = "manifest.txt";
+/// The default count of keys with '--max-open', whose destinations are opened lazily by a pool.
+const DEFAULT_MAX_KEYS_POOLED: usize = 1024;
+/// The default count of keys without '--max-open', which stays below common limits of open files.
+const DEFAULT_MAX_KEYS_UNPOOLED: usize = 256;
+const DEFAULT_RETRIES: u32 = 3;
+const DEFAULT_CHECKPOINT_INTERVAL: NonZeroU64 = match NonZeroU64::new(100_000) {
+    Some(interval) => interval,
+    None => unreachable!(),
+};
+
 #[derive(Parser, Debug)]
 #[clap(about, author, version)]
+#[allow(clippy::struct_excessive_bools)]
 pub(crate) struct Cli {
     /// A factor to multiply the grouping size of the distribution.
     #[clap(short = 'f', long, default_value = "1")]
     pub(crate) line_factor: NonZeroUsize,
 
-    /// The file which should be splitted. Use '-' for piping the content to zsplit.
+    /// The file which should be splitted. Use '-' for piping the content to zsplit. A glob pattern
+    /// like 'app.log.*' reads all matching files in alphabetical order, where numbers are ordered
+    /// by value like 'app.log.2' before 'app.log.10'.
     #[clap(parse(from_os_str = Source::from_os_str), value_hint(ValueHint::FilePath))]
     pub(crate) source: Source,
 
+    /// Additional sources, which are read after SOURCE as one continuous stream. Glob patterns are
+    /// expanded like for SOURCE.
+    #[clap(
+        short = 's',
+        long = "source",
+        value_name = "SOURCE",
+        multiple_occurrences(true),
+        parse(from_os_str = Source::from_os_str),
+        value_hint(ValueHint::FilePath)
+    )]
+    pub(crate) additional_sources: Vec<Source>,
+
+    /// Restarts the distribution with the first destination for every source.
+    #[clap(long)]
+    pub(crate) restart_per_source: bool,
+
     /// A list of destinations for the splitted contents.
     #[clap(
         multiple_values(true),
         min_values(2),
-        required(true),
+        required_unless_present_any(&["count", "output-template"]),
+        conflicts_with_all(&["count", "output-template"]),
         parse(from_os_str),
         value_hint(ValueHint::FilePath)
     )]
     pub(crate) destinations: Vec<PathBuf>,
 
+    /// Generates this count of destinations instead of listing them. They are named by PREFIX and
+    /// a suffix like 'xaa', 'xab', ... or by TEMPLATE.
+    #[clap(short = 'n', long)]
+    pub(crate) count: Option<NonZeroUsize>,
+
+    /// The prefix of generated destinations. It defaults to 'x'.
+    #[clap(
+        long,
+        requires("count"),
+        parse(from_os_str),
+        value_hint(ValueHint::FilePath)
+    )]
+    pub(crate) prefix: Option<OsString>,
+
+    /// The length of the suffix of generated destinations. It defaults to 2.
+    #[clap(short = 'a', long, requires("count"))]
+    pub(crate) suffix_length: Option<NonZeroUsize>,
+
+    /// Uses numeric suffixes starting at 0 instead of alphabetic ones for generated destinations.
+    #[clap(long, requires("count"))]
+    pub(crate) numeric_suffixes: bool,
+
+    /// Names generated destinations by a template like 'out_{index:03}.txt' instead of PREFIX and
+    /// a suffix. The index starts at 0.
+    #[clap(
+        long,
+        requires("count"),
+        conflicts_with_all(&["prefix", "suffix-length", "numeric-suffixes"])
+    )]
+    pub(crate) template: Option<Template>,
+
+    /// Generates the destinations inside this directory. It is created with its missing parents.
+    #[clap(
+        short = 'o',
+        long,
+        requires("count"),
+        parse(from_os_str),
+        value_hint(ValueHint::DirPath)
+    )]
+    pub(crate) output_dir: Option<PathBuf>,
+
+    /// Names the destination of every record by its key like 'users/{key}.log' instead of
+    /// distributing the records. A destination is created, when its key appears first. Characters
+    /// of the key except 'a-z', 'A-Z', '0-9', '-', '_' and '.' are replaced by '_'.
+    #[clap(long, conflicts_with_all(&["count", "distributions"]))]
+    pub(crate) output_template: Option<KeyTemplate>,
+
+    /// The field of a record, which is its key. The first field is 1.
+    #[clap(long, value_name = "FIELD", requires("output-template"))]
+    pub(crate) key_field: Option<NonZeroUsize>,
+
+    /// The delimiter of the fields of a record. It defaults to whitespace.
+    #[clap(long, value_name = "DELIMITER", requires("output-template"))]
+    pub(crate) key_delimiter: Option<char>,
+
+    /// The maximal count of distinct keys. It defaults to 1024 with '--max-open' and to 256
+    /// otherwise, since every key keeps its destination open. Keys, whose paths are equal after
+    /// replacing unsafe characters, fail the split.
+    #[clap(long, requires("output-template"))]
+    pub(crate) max_keys: Option<NonZeroUsize>,
+
+    /// Writes a 'manifest.txt' listing the generated destinations into the output directory.
+    #[clap(long, requires("output-dir"))]
+    pub(crate) manifest: bool,
+
+    /// Writes a JSON manifest of the split operation to this file. It contains the sources and
+    /// destinations with their sizes and SHA-256 checksums of the uncompressed content.
+    #[clap(
+        long,
+        value_name = "FILE",
+        parse(from_os_str),
+        value_hint(ValueHint::FilePath)
+    )]
+    pub(crate) json_manifest: Option<PathBuf>,
+
     /// Defines how many lines are assigned to a destination. The distributions have to be in the
     /// same order as the destinations. It defaults to 1.
     #[clap(short, long, multiple_values(true), min_values(0))]
     pub(crate) distributions: Vec<NonZeroUsize>,
+
+    /// Appends to existing destinations instead of overwriting them.
+    #[clap(long, conflicts_with_all(&["no-clobber", "json-manifest"]))]
+    pub(crate) append: bool,
+
+    /// Fails instead of overwriting an existing destination.
+    #[clap(long)]
+    pub(crate) no_clobber: bool,
+
+    /// Rotates a destination to 'NAME.1', 'NAME.2', ..., before it would exceed this count of
+    /// bytes.
+    #[clap(long, value_name = "BYTES", conflicts_with("json-manifest"))]
+    pub(crate) rotate_bytes: Option<NonZeroU64>,
+
+    /// Rotates a destination to 'NAME.1', 'NAME.2', ..., when it reached this count of lines.
+    #[clap(long, value_name = "LINES", conflicts_with("json-manifest"))]
+    pub(crate) rotate_lines: Option<NonZeroU64>,
+
+    /// Opens destinations on demand and keeps at most this count of them open. A closed
+    /// destination is reopened in append mode, so it can't be compressed.
+    #[clap(
+        long,
+        value_name = "FILES",
+        conflicts_with_all(&["rotate-bytes", "rotate-lines", "atomic", "compress"])
+    )]
+    pub(crate) max_open: Option<NonZeroUsize>,
+
+    /// Writes every destination into a temporary file first, which is renamed after the split
+    /// succeeded. A failed split doesn't leave truncated destinations behind.
+    #[clap(long)]
+    pub(crate) atomic: bool,
+
+    /// What happens, when writing to a destination fails. 'skip' drops the destination and
+    /// distributes its records among the remaining ones. It defaults to 'fail'.
+    #[clap(
+        long,
+        value_enum,
+        value_name = "POLICY",
+        conflicts_with("output-template")
+    )]
+    pub(crate) on_error: Option<OnError>,
+
+    /// The count of repetitions of a failed write with '--on-error retry'. The delay between them
+    /// starts at 10 ms and doubles with every repetition. It defaults to 3.
+    #[clap(long, requires("on-error"))]
+    pub(crate) retries: Option<NonZeroU32>,
+
+    /// Saves the progress of the split into this state file, which is removed after the split
+    /// succeeded. It requires a single uncompressed source file and uncompressed destinations.
+    #[clap(
+        long,
+        value_name = "STATE_FILE",
+        value_hint = ValueHint::FilePath,
+        conflicts_with_all(&[
+            "additional-sources",
+            "output-template",
+            "append",
+            "atomic",
+            "max-open",
+            "rotate-bytes",
+            "rotate-lines",
+            "json-manifest"
+        ])
+    )]
+    pub(crate) checkpoint: Option<PathBuf>,
+
+    /// Saves the progress after this count of records. It defaults to 100000.
+    #[clap(long, value_name = "RECORDS")]
+    pub(crate) checkpoint_interval: Option<NonZeroU64>,
+
+    /// Continues the split from the state file of '--checkpoint', if it exists. The destinations
+    /// are truncated to their length at the checkpoint.
+    #[clap(long, requires("checkpoint"))]
+    pub(crate) resume: bool,
+
+    /// Keeps reading the single source file like 'tail -f' and distributes new records as they
+    /// arrive. A truncated or rotated source is read again from its start. The destinations are
+    /// flushed, whenever no new records are available. They can't be compressed, since they are
+    /// never finished.
+    #[clap(
+        long,
+        conflicts_with_all(&[
+            "additional-sources",
+            "restart-per-source",
+            "output-template",
+            "atomic",
+            "compress",
+            "checkpoint",
+            "json-manifest"
+        ])
+    )]
+    pub(crate) follow: bool,
+
+    /// Writes contiguous chunks of the single uncompressed source file to the destinations in
+    /// parallel, each by its own thread. The sizes of the chunks are proportional to the
+    /// distributions and no record is split between two chunks.
+    #[clap(
+        long,
+        conflicts_with_all(&[
+            "additional-sources",
+            "restart-per-source",
+            "output-template",
+            "on-error",
+            "max-open",
+            "checkpoint",
+            "follow",
+            "json-manifest"
+        ])
+    )]
+    pub(crate) contiguous: bool,
+
+    /// When the destinations are flushed during the split. 'record' flushes after every record,
+    /// a count like '100' after this count of records and milliseconds like '250ms' with the
+    /// first record after this duration. It defaults to 'end'.
+    #[clap(long, value_name = "POLICY")]
+    pub(crate) flush: Option<FlushPolicy>,
+
+    /// Skips this count of records at the start before splitting.
+    #[clap(
+        long,
+        value_name = "RECORDS",
+        conflicts_with_all(&["lines", "checkpoint", "contiguous"])
+    )]
+    pub(crate) skip: Option<u64>,
+
+    /// Stops after splitting this count of records.
+    #[clap(
+        long,
+        value_name = "RECORDS",
+        conflicts_with_all(&["lines", "checkpoint", "contiguous", "json-manifest"])
+    )]
+    pub(crate) head: Option<u64>,
+
+    /// Splits only the records in this range like '1000..5000'. The records are numbered from 0
+    /// and the end is excluded. Either bound could be omitted like '1000..'.
+    #[clap(
+        long,
+        value_name = "RANGE",
+        conflicts_with_all(&["checkpoint", "contiguous", "json-manifest"])
+    )]
+    pub(crate) lines: Option<RecordRange>,
+
+    /// Limits every record to this count of bytes without its line ending, so an unbounded long
+    /// line can't stop the split.
+    #[clap(
+        long,
+        value_name = "BYTES",
+        conflicts_with_all(&["checkpoint", "contiguous", "json-manifest"])
+    )]
+    pub(crate) max_record_length: Option<NonZeroUsize>,
+
+    /// What happens with a record exceeding '--max-record-length'. 'truncate' drops its end and
+    /// 'stream' writes it completely in fragments. It defaults to 'fail'.
+    #[clap(long, value_enum, value_name = "POLICY", requires("max-record-length"))]
+    pub(crate) oversized: Option<Oversized>,
+
+    /// Drops every record, which appeared before in any source. A hash of every distinct record
+    /// is kept in memory. '--skip', '--head' and '--lines' count only the distinct records.
+    #[clap(long, conflicts_with_all(&["checkpoint", "contiguous", "annotate"]))]
+    pub(crate) dedup: bool,
+
+    /// Bounds the memory of '--dedup' by a Bloom filter of this count of bytes. About 1% of the
+    /// distinct records are dropped as well, when their count reaches a tenth of the bits.
+    #[clap(long, value_name = "BYTES", requires("dedup"))]
+    pub(crate) dedup_memory: Option<NonZeroUsize>,
+
+    /// Writes the records dropped by '--dedup' to this file.
+    #[clap(
+        long,
+        value_name = "FILE",
+        parse(from_os_str),
+        value_hint(ValueHint::FilePath),
+        requires("dedup"),
+        conflicts_with("follow")
+    )]
+    pub(crate) duplicates: Option<PathBuf>,
+
+    /// Prefixes every record with these fields of its origin like 'number,offset'. 'source' is
+    /// the source file, 'number' the number of the record in it from 0 and 'offset' its byte
+    /// offset in it.
+    #[clap(
+        long,
+        value_enum,
+        value_name = "FIELDS",
+        use_value_delimiter(true),
+        conflicts_with("contiguous")
+    )]
+    pub(crate) annotate: Vec<Field>,
+
+    /// The separator after every field of '--annotate'. It defaults to a tab.
+    #[clap(long, value_name = "SEPARATOR", requires("annotate"))]
+    pub(crate) annotation_separator: Option<String>,
+
+    /// Prints statistics of the split operation to STDERR.
+    #[clap(long)]
+    pub(crate) stats: bool,
+
+    /// Compresses every destination with this format instead of detecting it by the file
+    /// extension. Use 'none' to disable the compression.
+    #[clap(long, value_name = "FORMAT")]
+    pub(crate) compress: Option<Compression>,
+
+    /// The source, whose records are split, and its start.
+    #[clap(skip)]
+    pub(crate) source_position: Arc<SourcePosition>,
+}
+
+/// The CLI names of the [`OversizedRecords`].
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Oversized {
+    Fail,
+    Truncate,
+    Stream,
+}
+
+/// The CLI names of the [`ErrorPolicy`].
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OnError {
+    Fail,
+    Skip,
+    Retry,
 }
 
 impl Cli {
     pub(crate) fn validate(&self) -> crate::Result<()> {
-        if let Source::PathBuf(source) = &self.source {
-            self.destinations
-                .iter()
-                .all(|destination| source != destination)
-                .err(crate::Error::FileDuplicate)?;
-        }
+        let destinations = self.destination_paths()?;
+        let sources = self.sources()?;
 
-        let (destinations_len, distributions_len) =
-            (self.destinations.len(), self.distributions.len());
+        let outputs = destinations
+            .iter()
+            .chain(&self.duplicates)
+            .chain(&self.checkpoint)
+            .cloned()
+            .collect::<Vec<_>>();
+        validate_sources(&sources, &outputs)?;
+        validate_output(self.duplicates.as_deref(), &destinations)?;
+        validate_output(self.checkpoint.as_deref(), &destinations)?;
+        self.validate_annotation()?;
+        self.validate_uncompressed(&destinations)?;
+
+        let (destinations_len, distributions_len) = (destinations.len(), self.distributions.len());
         (destinations_len >= distributions_len).err(
             crate::Error::MoreDistributionsAsDestinations {
                 destinations_len,
@@ -59,28 +413,669 @@ impl Cli {
         Ok(())
     }
 
+    /// Fails, when one of the `destinations` would be compressed, although it's reopened by
+    /// '--max-open' or never finished by '--follow'.
+    fn validate_uncompressed(&self, destinations: &[PathBuf]) -> crate::Result<()> {
+        let option = match (self.max_open, self.follow) {
+            (Some(_), _) => "--max-open",
+            (None, true) => "--follow",
+            (None, false) => return Ok(()),
+        };
+
+        let compressed = destinations
+            .iter()
+            .find(|path| Compression::from_path(path) != Compression::None);
+        match compressed {
+            Some(path) => Err(error_stack::Report::new(crate::Error::Destination)
+                .attach_printable(format!(
+                    "The destination `{}` can't be compressed with '{option}'",
+                    path.display()
+                ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks, whether the requested fields of the annotation are known.
+    fn validate_annotation(&self) -> crate::Result<()> {
+        let skipped = self.record_range().map_or(0, |range| range.start);
+        let truncated = self.oversized == Some(Oversized::Truncate);
+        (!self.annotate.contains(&Field::Offset) || (skipped == 0 && !truncated))
+            .err(crate::Error::UnknownOffsets)?;
+
+        Ok(())
+    }
+
+    /// Replaces the glob patterns of the sources by their matching files, so every later use sees
+    /// the same files.
+    pub(crate) fn expand_sources(&mut self) -> crate::Result<()> {
+        let mut sources = self.sources()?.into_iter();
+
+        if let Some(source) = sources.next() {
+            self.source = source;
+        }
+        self.additional_sources = sources.collect();
+        Ok(())
+    }
+
+    /// All sources with expanded glob patterns.
+    pub(crate) fn sources(&self) -> crate::Result<Vec<Source>> {
+        std::iter::once(&self.source)
+            .chain(&self.additional_sources)
+            .map(Source::expand)
+            .collect::<crate::Result<Vec<_>>>()
+            .map(|sources| sources.into_iter().flatten().collect())
+    }
+
+    /// The listed or generated paths of the destinations.
+    pub(crate) fn destination_paths(&self) -> crate::Result<Vec<PathBuf>> {
+        let Some(count) = self.count else {
+            return Ok(self.destinations.clone());
+        };
+
+        (0..count.get())
+            .map(|index| self.generated_path(index))
+            .collect()
+    }
+
+    fn generated_path(&self, index: usize) -> crate::Result<PathBuf> {
+        let name = self.generated_name(index)?;
+
+        Ok(match &self.output_dir {
+            Some(output_dir) => output_dir.join(name),
+            None => name,
+        })
+    }
+
+    fn generated_name(&self, index: usize) -> crate::Result<PathBuf> {
+        if let Some(template) = &self.template {
+            return Ok(template.path(index));
+        }
+
+        let suffix_length = self.suffix_length.map_or(2, usize::from);
+        let suffix = naming::suffix(index, suffix_length, self.numeric_suffixes)
+            .ok_or(crate::Error::SuffixesExhausted { suffix_length })?;
+
+        let mut path = self.prefix.clone().unwrap_or_else(|| "x".into());
+        path.push(suffix);
+        Ok(path.into())
+    }
+
     pub(crate) fn destinations(
         &self,
-    ) -> crate::Result<Vec<Destination<impl io::Write + std::fmt::Debug>>> {
-        self.destinations
-            .iter()
+    ) -> crate::Result<Vec<Destination<Checksummed<impl Finish + std::fmt::Debug>>>> {
+        self.destinations_with(self.file_options())
+    }
+
+    /// Opens the destinations with `options`.
+    pub(crate) fn destinations_with(
+        &self,
+        options: FileOptions,
+    ) -> crate::Result<Vec<Destination<Checksummed<impl Finish + std::fmt::Debug>>>> {
+        let pool = self.file_pool(options);
+
+        self.destinations_by(|file| self.open_sink(file, &options, pool.as_ref()))
+    }
+
+    /// Opens the destinations without a pool, so they can be written by separate threads.
+    pub(crate) fn contiguous_destinations(
+        &self,
+    ) -> crate::Result<Vec<Destination<Checksummed<impl Finish + std::fmt::Debug + Send>>>> {
+        let options = self.file_options();
+
+        self.destinations_by(|file| {
+            open_destination(file, &options)
+                .map(|sink| Checksummed::new(sink, self.json_manifest.is_some()))
+        })
+    }
+
+    /// Opens the sinks of the destinations with `open`.
+    fn destinations_by<S: std::io::Write>(
+        &self,
+        mut open: impl FnMut(&Path) -> std::io::Result<S>,
+    ) -> crate::Result<Vec<Destination<S>>> {
+        let annotation = self.annotation()?;
+
+        self.destination_paths()?
+            .into_iter()
             .enumerate()
             .map(|(index, file)| {
-                Destination::new_with_path_and_lines(
-                    file.clone(),
-                    usize::from(self.line_factor) * self.get_distribution(index),
-                )
-                .change_context(crate::Error::Destination)
-                .attach_printable_lazy(|| {
-                    format!("Couldn't open file `{}` as writable", file.display())
-                })
+                open(&file)
+                    .map(|sink| {
+                        let destination = Destination::new(
+                            sink,
+                            usize::from(self.line_factor) * self.get_distribution(index),
+                        )
+                        .flush_policy(self.flush_policy());
+                        annotate(destination, annotation.as_ref())
+                    })
+                    .change_context(crate::Error::Destination)
+                    .attach_printable_lazy(|| {
+                        format!("Couldn't open file `{}` as writable", file.display())
+                    })
             })
             .collect()
     }
 
+    /// The annotation of the written records, if requested.
+    pub(crate) fn annotation(&self) -> crate::Result<Option<Annotation>> {
+        if self.annotate.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Annotation::new(
+            self.annotate.clone(),
+            self.annotation_separator
+                .clone()
+                .unwrap_or_else(|| "\t".to_owned()),
+            self.record_range().map_or(0, |range| range.start),
+            &self.sources()?,
+            Arc::clone(&self.source_position),
+        )))
+    }
+
+    /// The deduplication of the records, if requested.
+    pub(crate) fn deduplication(
+        &self,
+    ) -> crate::Result<Option<Deduplication<impl Finish + std::fmt::Debug>>> {
+        if !self.dedup {
+            return Ok(None);
+        }
+
+        let filter = self
+            .dedup_memory
+            .map_or(DuplicateFilter::Exact, DuplicateFilter::Bloom);
+        let duplicates = self
+            .duplicates
+            .as_ref()
+            .map(|file| {
+                open_destination(file, &self.file_options())
+                    .change_context(crate::Error::Destination)
+                    .attach_printable_lazy(|| {
+                        format!("Couldn't open file `{}` as writable", file.display())
+                    })
+            })
+            .transpose()?;
+
+        Ok(Some(Deduplication::new(filter, duplicates)))
+    }
+
+    /// Finishes the file of the duplicates, if any.
+    pub(crate) fn finish_deduplication(
+        &self,
+        deduplication: Option<Deduplication<impl Finish>>,
+    ) -> crate::Result<()> {
+        deduplication
+            .map_or(Ok(()), Deduplication::finish)
+            .change_context(crate::Error::Destination)
+            .attach_printable_lazy(|| {
+                let file = self.duplicates.as_deref().unwrap_or_else(|| Path::new(""));
+                format!("Couldn't finish file `{}`", file.display())
+            })
+    }
+
+    /// Annotates the following records with the source at `index`, whose records are split
+    /// separately.
+    pub(crate) fn enter_source(&self, index: usize) {
+        self.source_position.enter(index, 0, 0);
+    }
+
+    /// Tracks the source of the records read from the `chain` for the annotation, if requested.
+    pub(crate) fn track_sources<R: BufRead>(&self, chain: SourceChain<R>) -> SourceChain<R> {
+        if self.annotate.is_empty() {
+            return chain;
+        }
+        chain.track(Arc::clone(&self.source_position))
+    }
+
+    /// Opens the sink of a destination directly or lazily by the `pool`.
+    pub(crate) fn open_sink(
+        &self,
+        file: &Path,
+        options: &FileOptions,
+        pool: Option<&FilePool>,
+    ) -> std::io::Result<Checksummed<impl Finish + std::fmt::Debug>> {
+        let sink = match pool {
+            Some(pool) => DestinationSink::Pooled(pool.sink(file)),
+            None => DestinationSink::Direct(open_destination(file, options)?),
+        };
+
+        Ok(Checksummed::new(sink, self.json_manifest.is_some()))
+    }
+
+    pub(crate) fn file_pool(&self, options: FileOptions) -> Option<FilePool> {
+        self.max_open
+            .map(|max_open| FilePool::new(max_open, options))
+    }
+
+    /// Opens the sink of the destination of `key` like [`Cli::open_sink`].
+    ///
+    /// It fails, when the path of `key` was already opened for another key in `opened`.
+    pub(crate) fn open_key_sink(
+        &self,
+        key: &str,
+        options: &FileOptions,
+        pool: Option<&FilePool>,
+        opened: &mut BTreeMap<PathBuf, String>,
+    ) -> std::io::Result<Checksummed<impl Finish + std::fmt::Debug>> {
+        let file = self.key_path(key);
+        if let Some(other) = opened.get(&file) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!(
+                    "The keys `{other}` and `{key}` share the file `{}`",
+                    file.display()
+                ),
+            ));
+        }
+        opened.insert(file.clone(), key.to_owned());
+
+        self.open_sink(&file, options, pool).map_err(|error| {
+            std::io::Error::new(
+                error.kind(),
+                format!(
+                    "Couldn't open file `{}` as writable: {error}",
+                    file.display()
+                ),
+            )
+        })
+    }
+
+    /// The path of the destination of `key`.
+    pub(crate) fn key_path(&self, key: &str) -> PathBuf {
+        self.output_template
+            .as_ref()
+            .map_or_else(|| key.into(), |template| template.path(key))
+    }
+
+    /// The key of a record for the output template.
+    pub(crate) fn key_of<'a>(&self, line: &'a str) -> &'a str {
+        let index = self.key_field.map_or(0, |key_field| key_field.get() - 1);
+
+        match self.key_delimiter {
+            Some(delimiter) => line.split(delimiter).nth(index),
+            None => line.split_whitespace().nth(index),
+        }
+        .unwrap_or_default()
+    }
+
+    pub(crate) fn flush_policy(&self) -> FlushPolicy {
+        self.flush.unwrap_or_default()
+    }
+
+    pub(crate) fn max_keys(&self) -> usize {
+        let default = match self.max_open {
+            Some(_) => DEFAULT_MAX_KEYS_POOLED,
+            None => DEFAULT_MAX_KEYS_UNPOOLED,
+        };
+
+        self.max_keys.map_or(default, NonZeroUsize::get)
+    }
+
+    /// Finishes all destinations, before any of them replaces its file in the atomic mode.
+    pub(crate) fn finish_destinations(
+        paths: &[PathBuf],
+        destinations: Vec<Destination<impl Finish>>,
+    ) -> crate::Result<()> {
+        let staged = destinations
+            .into_iter()
+            .zip(paths)
+            .map(|(destination, file)| {
+                destination
+                    .finish_staged()
+                    .change_context(crate::Error::Destination)
+                    .attach_printable_lazy(|| format!("Couldn't finish file `{}`", file.display()))
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        staged
+            .into_iter()
+            .zip(paths)
+            .try_for_each(|(staged, file)| {
+                staged
+                    .persist()
+                    .change_context(crate::Error::Destination)
+                    .attach_printable_lazy(|| format!("Couldn't replace file `{}`", file.display()))
+            })
+    }
+
+    /// Fails with every destination dropped by the error policy.
+    pub(crate) fn check_failures(paths: &[PathBuf], report: &SplitReport) -> crate::Result<()> {
+        if report.failures.is_empty() {
+            return Ok(());
+        }
+
+        let error = report.failures.iter().fold(
+            error_stack::Report::new(crate::Error::DroppedDestinations {
+                count: report.failures.len(),
+            }),
+            |error, failure| {
+                error.attach_printable(format!(
+                    "Dropped `{}` at record {}: {}",
+                    paths[failure.index].display(),
+                    failure.record,
+                    failure.message
+                ))
+            },
+        );
+        Err(error.attach(sysexits::ExitCode::IoErr))
+    }
+
+    /// Writes the manifest of the generated destinations into the output directory, if requested.
+    pub(crate) fn write_manifest(&self) -> crate::Result<()> {
+        let (Some(output_dir), Some(count)) = (&self.output_dir, self.count) else {
+            return Ok(());
+        };
+        if !self.manifest {
+            return Ok(());
+        }
+
+        let manifest = (0..count.get())
+            .map(|index| {
+                self.generated_name(index)
+                    .map(|name| format!("{}\n", name.display()))
+            })
+            .collect::<crate::Result<String>>()?;
+
+        let manifest_path = output_dir.join(MANIFEST_FILE_NAME);
+        std::fs::write(&manifest_path, manifest)
+            .change_context(crate::Error::Manifest)
+            .attach_printable_lazy(|| format!("Couldn't write file `{}`", manifest_path.display()))
+    }
+
+    /// The JSON manifest of the finished split, if requested.
+    pub(crate) fn json_manifest<R: BufRead, W: Finish>(
+        &self,
+        sources: &[Source],
+        readers: &[Checksummed<R>],
+        paths: &[PathBuf],
+        destinations: &[Destination<Checksummed<W>>],
+    ) -> Option<Manifest> {
+        self.json_manifest.as_ref()?;
+
+        let distributions = self.destination_distributions(destinations.len());
+        let sources = sources
+            .iter()
+            .zip(readers)
+            .map(|(source, reader)| SourceEntry::new(source, reader.checksum().unwrap_or_default()))
+            .collect();
+        let destinations = paths
+            .iter()
+            .zip(destinations)
+            .map(|(path, destination)| {
+                DestinationEntry::new(path, destination.sink.checksum().unwrap_or_default())
+            })
+            .collect();
+
+        Some(Manifest {
+            sources,
+            strategy: self.strategy(),
+            restart_per_source: self.restart_per_source,
+            line_factor: self.line_factor.get(),
+            distributions,
+            destinations,
+        })
+    }
+
+    /// Writes the JSON manifest to the requested file.
+    pub(crate) fn write_json_manifest(&self, manifest: Option<Manifest>) -> crate::Result<()> {
+        let (Some(path), Some(manifest)) = (&self.json_manifest, manifest) else {
+            return Ok(());
+        };
+
+        manifest
+            .write(path)
+            .change_context(crate::Error::Manifest)
+            .attach_printable_lazy(|| format!("Couldn't write file `{}`", path.display()))
+    }
+
+    /// Prints the statistics of the split operation to STDERR, if requested.
+    pub(crate) fn print_stats(&self, paths: &[PathBuf], report: &SplitReport) {
+        if self.stats {
+            for line in stats_lines(paths, report) {
+                eprintln!("{line}");
+            }
+        }
+    }
+
+    fn strategy(&self) -> &'static str {
+        if self.output_template.is_some() {
+            "by_key"
+        } else {
+            "round_robin"
+        }
+    }
+
+    pub(crate) fn file_options(&self) -> FileOptions {
+        let options = FileOptions::new()
+            .create_parents(self.output_dir.is_some() || self.output_template.is_some())
+            .atomic(self.atomic)
+            .policy(self.write_policy());
+
+        let options = match self.rotation() {
+            Some(rotation) => options.rotation(rotation),
+            None => options,
+        };
+
+        match self.compress {
+            Some(compression) => options.compression(compression),
+            None => options,
+        }
+    }
+
+    fn rotation(&self) -> Option<Rotation> {
+        if self.rotate_bytes.is_none() && self.rotate_lines.is_none() {
+            return None;
+        }
+
+        let rotation = Rotation::new();
+        let rotation = match self.rotate_bytes {
+            Some(max_bytes) => rotation.max_bytes(max_bytes.get()),
+            None => rotation,
+        };
+        Some(match self.rotate_lines {
+            Some(max_lines) => rotation.max_lines(max_lines.get()),
+            None => rotation,
+        })
+    }
+
+    pub(crate) fn error_policy(&self) -> ErrorPolicy {
+        match self.on_error {
+            None | Some(OnError::Fail) => ErrorPolicy::FailFast,
+            Some(OnError::Skip) => ErrorPolicy::Skip,
+            Some(OnError::Retry) => {
+                ErrorPolicy::Retry(self.retries.map_or(DEFAULT_RETRIES, NonZeroU32::get))
+            }
+        }
+    }
+
+    /// Selects the records of the `source` in the requested range.
+    ///
+    /// With '--restart-per-source' the range applies to every source.
+    pub(crate) fn select_records<'a>(&self, source: impl BufRead + 'a) -> Box<dyn BufRead + 'a> {
+        match self.record_range() {
+            Some(range) => Box::new(SelectedRecords::new(source, range)),
+            None => Box::new(source),
+        }
+    }
+
+    fn record_range(&self) -> Option<RecordRange> {
+        if self.lines.is_some() || (self.skip.is_none() && self.head.is_none()) {
+            return self.lines;
+        }
+
+        let start = self.skip.unwrap_or(0);
+        let end = self.head.map(|head| start.saturating_add(head));
+        Some(RecordRange::new(start, end))
+    }
+
+    /// Limits the length of the records of the `source`, if requested.
+    pub(crate) fn limit_records<'a>(&self, source: Box<dyn BufRead + 'a>) -> Box<dyn BufRead + 'a> {
+        let Some(max_len) = self.max_record_length else {
+            return source;
+        };
+        let oversized = match self.oversized {
+            None | Some(Oversized::Fail) => OversizedRecords::Fail,
+            Some(Oversized::Truncate) => OversizedRecords::Truncate,
+            Some(Oversized::Stream) => OversizedRecords::Stream,
+        };
+
+        Box::new(LimitedRecords::new(source, max_len).oversized(oversized))
+    }
+
+    pub(crate) fn checkpoint_interval(&self) -> NonZeroU64 {
+        self.checkpoint_interval
+            .unwrap_or(DEFAULT_CHECKPOINT_INTERVAL)
+    }
+
+    /// Fails, when the split into `paths` can't be resumed by byte offsets.
+    pub(crate) fn validate_resumable(&self, paths: &[PathBuf]) -> crate::Result<()> {
+        let compressed = paths.iter().find(|path| {
+            self.compress
+                .unwrap_or_else(|| Compression::from_path(path))
+                != Compression::None
+        });
+        if let Some(path) = compressed {
+            return Err(error_stack::Report::new(crate::Error::Checkpoint)
+                .attach_printable(format!(
+                    "The compressed destination `{}` can't be resumed",
+                    path.display()
+                ))
+                .attach(sysexits::ExitCode::Usage));
+        }
+
+        (self.error_policy() != ErrorPolicy::Skip)
+            .err(crate::Error::Checkpoint)
+            .attach_printable("Dropped destinations can't be resumed")
+            .attach(sysexits::ExitCode::Usage)
+    }
+
+    /// The state of the checkpoint of the split of `source` into `paths`, if it is resumed.
+    pub(crate) fn resume_state(
+        &self,
+        source: &Path,
+        paths: &[PathBuf],
+    ) -> crate::Result<Option<State>> {
+        let Some(state_file) = self.checkpoint.as_ref().filter(|_| self.resume) else {
+            return Ok(None);
+        };
+
+        let state = State::load(state_file)
+            .change_context(crate::Error::Checkpoint)
+            .attach_printable_lazy(|| format!("Couldn't read file `{}`", state_file.display()))?;
+
+        match state {
+            Some(state)
+                if !state.matches(
+                    source,
+                    self.line_factor.get(),
+                    &self.destination_distributions(paths.len()),
+                    paths,
+                ) =>
+            {
+                Err(error_stack::Report::new(crate::Error::Checkpoint)
+                    .attach_printable(format!(
+                        "The file `{}` belongs to another split",
+                        state_file.display()
+                    ))
+                    .attach(sysexits::ExitCode::DataErr))
+            }
+            state => Ok(state),
+        }
+    }
+
+    fn write_policy(&self) -> WritePolicy {
+        if self.append {
+            WritePolicy::Append
+        } else if self.no_clobber {
+            WritePolicy::FailIfExists
+        } else {
+            WritePolicy::Overwrite
+        }
+    }
+
+    /// The distributions of `len` destinations including the default ones.
+    pub(crate) fn destination_distributions(&self, len: usize) -> Vec<usize> {
+        (0..len).map(|index| self.get_distribution(index)).collect()
+    }
+
     fn get_distribution(&self, index: usize) -> usize {
         self.distributions
             .get(index)
             .map_or(1, |distribution| usize::from(*distribution))
     }
 }
+
+fn stats_lines(paths: &[PathBuf], report: &SplitReport) -> Vec<String> {
+    let throughput = report
+        .throughput()
+        .map_or_else(|| "-".to_owned(), |throughput| format!("{throughput} B/s"));
+
+    let summary = [
+        format!("Records written: {}", report.records_written),
+        format!("Bytes written: {}", report.bytes_written()),
+        format!("Elapsed: {:.3?}", report.elapsed),
+        format!("Throughput: {throughput}"),
+    ];
+    let destinations = paths
+        .iter()
+        .zip(&report.destinations)
+        .map(|(path, destination)| {
+            format!(
+                "{}: {} records, {} bytes",
+                path.display(),
+                destination.records,
+                destination.bytes
+            )
+        });
+
+    summary.into_iter().chain(destinations).collect()
+}
+
+/// Transforms the records of the `destination` with the `annotation`, if any.
+fn annotate<S: std::io::Write>(
+    destination: Destination<S>,
+    annotation: Option<&Annotation>,
+) -> Destination<S> {
+    match annotation {
+        Some(annotation) => destination.transform(annotation.clone()),
+        None => destination,
+    }
+}
+
+fn open_destination(
+    file: &Path,
+    options: &FileOptions,
+) -> std::io::Result<impl Finish + std::fmt::Debug> {
+    Destination::new_with_path_and_options(file, 1, options).map(Destination::into_sink)
+}
+
+/// Fails, when the additional `output` is also one of the `destinations`.
+fn validate_output(output: Option<&Path>, destinations: &[PathBuf]) -> crate::Result<()> {
+    let Some(output) = output else {
+        return Ok(());
+    };
+
+    destinations
+        .iter()
+        .all(|destination| destination != output)
+        .err(crate::Error::OutputDuplicate)
+        .attach_printable_lazy(|| format!("The file `{}` is written twice", output.display()))
+}
+
+fn validate_sources(sources: &[Source], destinations: &[PathBuf]) -> crate::Result<()> {
+    sources.iter().try_for_each(|source| match source {
+        Source::PathBuf(source) => destinations
+            .iter()
+            .all(|destination| source != destination)
+            .err(crate::Error::FileDuplicate),
+        Source::Glob(_) | Source::StdIn => Ok(()),
+    })?;
+
+    let stdin_count = sources
+        .iter()
+        .filter(|source| **source == Source::StdIn)
+        .count();
+    (stdin_count <= 1).err(crate::Error::StdInDuplicate)?;
+
+    Ok(())
+}
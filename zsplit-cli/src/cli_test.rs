@@ -1,13 +1,55 @@
 use super::*;
 use crate::Error;
 use std::convert::TryInto;
+use std::io::Read;
 
 fn empty_cli() -> Cli {
     Cli {
         line_factor: non_zero_usize(1),
         source: Source::PathBuf(PathBuf::new()),
+        additional_sources: Vec::new(),
+        restart_per_source: false,
         destinations: vec![PathBuf::new(), PathBuf::new(), PathBuf::new()],
+        count: None,
+        prefix: None,
+        suffix_length: None,
+        numeric_suffixes: false,
+        template: None,
+        output_dir: None,
+        output_template: None,
+        key_field: None,
+        key_delimiter: None,
+        max_keys: None,
+        manifest: false,
+        json_manifest: None,
         distributions: Vec::new(),
+        rotate_bytes: None,
+        rotate_lines: None,
+        max_open: None,
+        append: false,
+        no_clobber: false,
+        atomic: false,
+        on_error: None,
+        retries: None,
+        checkpoint: None,
+        checkpoint_interval: None,
+        resume: false,
+        follow: false,
+        contiguous: false,
+        flush: None,
+        skip: None,
+        head: None,
+        lines: None,
+        max_record_length: None,
+        oversized: None,
+        dedup: false,
+        dedup_memory: None,
+        duplicates: None,
+        annotate: Vec::new(),
+        annotation_separator: None,
+        stats: false,
+        compress: None,
+        source_position: Arc::default(),
     }
 }
 
@@ -16,6 +58,12 @@ fn non_zero_usize(num: usize) -> NonZeroUsize {
     num.try_into().unwrap()
 }
 
+#[test]
+fn verify_cli() {
+    use clap::CommandFactory;
+    Cli::command().debug_assert();
+}
+
 #[test]
 fn invalid_source_in_destinations() {
     let cli = empty_cli();
@@ -42,6 +90,126 @@ fn valid_source_stdin() {
     cli.validate().unwrap();
 }
 
+#[test]
+fn invalid_additional_source_in_destinations() {
+    let mut cli = empty_cli();
+    cli.source = Source::StdIn;
+    cli.additional_sources = vec![Source::PathBuf(PathBuf::new())];
+
+    assert_eq!(
+        *cli.validate().unwrap_err().current_context(),
+        Error::FileDuplicate
+    );
+}
+
+#[test]
+fn invalid_source_as_duplicates() {
+    let mut cli = empty_cli();
+    cli.source = Source::PathBuf(PathBuf::from("test.txt"));
+    cli.destinations = vec![PathBuf::from("a")];
+    cli.duplicates = Some(PathBuf::from("test.txt"));
+
+    assert_eq!(
+        *cli.validate().unwrap_err().current_context(),
+        Error::FileDuplicate
+    );
+}
+
+#[test]
+fn invalid_duplicates_in_destinations() {
+    let mut cli = empty_cli();
+    cli.source = Source::StdIn;
+    cli.destinations = vec![PathBuf::from("a"), PathBuf::from("b")];
+    cli.duplicates = Some(PathBuf::from("b"));
+
+    assert_eq!(
+        *cli.validate().unwrap_err().current_context(),
+        Error::OutputDuplicate
+    );
+}
+
+#[test]
+fn invalid_checkpoint_in_destinations() {
+    let mut cli = empty_cli();
+    cli.source = Source::PathBuf(PathBuf::from("test.txt"));
+    cli.destinations = vec![PathBuf::from("a"), PathBuf::from("b")];
+    cli.checkpoint = Some(PathBuf::from("a"));
+
+    assert_eq!(
+        *cli.validate().unwrap_err().current_context(),
+        Error::OutputDuplicate
+    );
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn invalid_compressed_pooled_destination() {
+    let mut cli = empty_cli();
+    cli.source = Source::StdIn;
+    cli.destinations = vec![PathBuf::from("a"), PathBuf::from("b.gz")];
+    cli.max_open = Some(non_zero_usize(1));
+
+    assert_eq!(
+        *cli.validate().unwrap_err().current_context(),
+        Error::Destination
+    );
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn invalid_compressed_followed_destination() {
+    let mut cli = empty_cli();
+    cli.source = Source::PathBuf(PathBuf::from("test.txt"));
+    cli.destinations = vec![PathBuf::from("a.zst")];
+    cli.follow = true;
+
+    assert_eq!(
+        *cli.validate().unwrap_err().current_context(),
+        Error::Destination
+    );
+}
+
+#[test]
+fn invalid_stdin_twice() {
+    let mut cli = empty_cli();
+    cli.source = Source::StdIn;
+    cli.additional_sources = vec![Source::PathBuf(PathBuf::from("test.txt")), Source::StdIn];
+
+    assert_eq!(
+        *cli.validate().unwrap_err().current_context(),
+        Error::StdInDuplicate
+    );
+}
+
+#[test]
+fn sources_in_order() {
+    let mut cli = empty_cli();
+    cli.source = Source::StdIn;
+    cli.additional_sources = vec![Source::PathBuf(PathBuf::from("test.txt"))];
+
+    assert_eq!(
+        cli.sources().unwrap(),
+        vec![Source::StdIn, Source::PathBuf(PathBuf::from("test.txt"))]
+    );
+}
+
+#[test]
+fn expand_sources_once() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a.txt"), "0\n").unwrap();
+    let mut cli = empty_cli();
+    cli.source = Source::StdIn;
+    cli.additional_sources = vec![Source::Glob(format!("{}/*.txt", dir.path().display()))];
+
+    cli.expand_sources().unwrap();
+    std::fs::write(dir.path().join("b.txt"), "1\n").unwrap();
+
+    assert_eq!(
+        cli.sources().unwrap(),
+        vec![Source::StdIn, Source::PathBuf(dir.path().join("a.txt"))]
+    );
+}
+
 #[test]
 fn valid_balanced_distributions_destinations() {
     let mut cli = empty_cli();
@@ -125,3 +293,395 @@ fn error_during_sink_creation() {
 
     let _ = cli.destinations().unwrap_err();
 }
+
+#[test]
+fn finish_default_destinations() {
+    let cli = empty_cli();
+
+    let destinations = cli.destinations().unwrap();
+
+    Cli::finish_destinations(&cli.destination_paths().unwrap(), destinations).unwrap();
+}
+
+#[test]
+fn parse_no_compression() {
+    let cli = Cli::try_parse_from(["zsplit", "-", "a", "b", "--compress", "none"]).unwrap();
+
+    assert_eq!(cli.compress, Some(Compression::None));
+}
+
+#[test]
+fn parse_unknown_compression() {
+    Cli::try_parse_from(["zsplit", "-", "a", "b", "--compress", "rar"]).unwrap_err();
+}
+
+#[test]
+fn parse_flush_policy() {
+    let cli = Cli::try_parse_from(["zsplit", "-", "a", "b", "--flush", "250ms"]).unwrap();
+
+    assert_eq!(
+        cli.flush_policy(),
+        FlushPolicy::Interval(std::time::Duration::from_millis(250))
+    );
+    assert_eq!(empty_cli().flush_policy(), FlushPolicy::AtEnd);
+}
+
+#[test]
+fn generated_destinations() {
+    let mut cli = empty_cli();
+    cli.destinations = Vec::new();
+    cli.count = Some(non_zero_usize(3));
+    cli.prefix = Some("out_".into());
+
+    assert_eq!(
+        cli.destination_paths().unwrap(),
+        vec![
+            PathBuf::from("out_aa"),
+            PathBuf::from("out_ab"),
+            PathBuf::from("out_ac")
+        ]
+    );
+}
+
+#[test]
+fn generated_numeric_destinations() {
+    let mut cli = empty_cli();
+    cli.destinations = Vec::new();
+    cli.count = Some(non_zero_usize(2));
+    cli.suffix_length = Some(non_zero_usize(3));
+    cli.numeric_suffixes = true;
+
+    assert_eq!(
+        cli.destination_paths().unwrap(),
+        vec![PathBuf::from("x000"), PathBuf::from("x001")]
+    );
+}
+
+#[test]
+fn generated_template_destinations() {
+    let mut cli = empty_cli();
+    cli.destinations = Vec::new();
+    cli.count = Some(non_zero_usize(2));
+    cli.template = Some("out_{index:03}.txt".parse().unwrap());
+
+    assert_eq!(
+        cli.destination_paths().unwrap(),
+        vec![PathBuf::from("out_000.txt"), PathBuf::from("out_001.txt")]
+    );
+}
+
+#[test]
+fn invalid_too_short_suffix_length() {
+    let mut cli = empty_cli();
+    cli.source = Source::StdIn;
+    cli.destinations = Vec::new();
+    cli.count = Some(non_zero_usize(27));
+    cli.suffix_length = Some(non_zero_usize(1));
+
+    assert_eq!(
+        *cli.validate().unwrap_err().current_context(),
+        Error::SuffixesExhausted { suffix_length: 1 }
+    );
+}
+
+#[test]
+fn parse_count_without_destinations() {
+    let cli = Cli::try_parse_from(["zsplit", "-", "--count", "64"]).unwrap();
+
+    assert_eq!(cli.destination_paths().unwrap().len(), 64);
+}
+
+#[test]
+fn parse_count_conflicts_with_destinations() {
+    Cli::try_parse_from(["zsplit", "-", "a", "b", "--count", "2"]).unwrap_err();
+}
+
+#[test]
+fn generated_destinations_in_output_dir() {
+    let mut cli = empty_cli();
+    cli.destinations = Vec::new();
+    cli.count = Some(non_zero_usize(2));
+    cli.output_dir = Some(PathBuf::from("shards"));
+
+    assert_eq!(
+        cli.destination_paths().unwrap(),
+        vec![PathBuf::from("shards/xaa"), PathBuf::from("shards/xab")]
+    );
+}
+
+#[test]
+fn no_manifest_without_output_dir() {
+    let mut cli = empty_cli();
+    cli.manifest = true;
+
+    cli.write_manifest().unwrap();
+}
+
+#[test]
+fn no_json_manifest_by_default() {
+    let cli = empty_cli();
+    let readers: [Checksummed<&[u8]>; 0] = [];
+    let destinations: [Destination<Checksummed<Vec<u8>>>; 0] = [];
+
+    assert!(cli
+        .json_manifest(&[], &readers, &[], &destinations)
+        .is_none());
+}
+
+#[test]
+fn json_manifest_of_split() {
+    let mut cli = empty_cli();
+    cli.source = Source::StdIn;
+    cli.destinations = vec![PathBuf::from("a"), PathBuf::from("b")];
+    cli.distributions = vec![non_zero_usize(2)];
+    cli.json_manifest = Some(PathBuf::from("manifest.json"));
+
+    let mut reader = Checksummed::new(&b"0\n1\n2\n"[..], true);
+    let mut destinations = vec![
+        Destination::new(Checksummed::new(Vec::new(), true), 2),
+        Destination::new(Checksummed::new(Vec::new(), true), 1),
+    ];
+    zsplit::split_round_robin(&mut reader, &mut destinations).unwrap();
+
+    let manifest = cli
+        .json_manifest(
+            &cli.sources().unwrap(),
+            &[reader],
+            &cli.destination_paths().unwrap(),
+            &destinations,
+        )
+        .unwrap();
+
+    assert_eq!(manifest.sources[0].path, PathBuf::from("-"));
+    assert_eq!(manifest.sources[0].size, 6);
+    assert_eq!(manifest.distributions, vec![2, 1]);
+    assert_eq!(manifest.destinations[0].path, PathBuf::from("a"));
+    assert_eq!(manifest.destinations[0].lines, 2);
+    assert_eq!(manifest.destinations[1].bytes, 2);
+}
+
+#[test]
+fn stats_of_split() {
+    let mut cli = empty_cli();
+    cli.destinations = vec![PathBuf::from("a"), PathBuf::from("b")];
+
+    let mut destinations = vec![Destination::buffer(), Destination::buffer()];
+    let mut report = zsplit::split_round_robin(&mut &b"0\n1\n2\n"[..], &mut destinations).unwrap();
+    report.elapsed = std::time::Duration::from_secs(2);
+
+    assert_eq!(
+        stats_lines(&cli.destination_paths().unwrap(), &report),
+        vec![
+            "Records written: 3",
+            "Bytes written: 6",
+            "Elapsed: 2.000s",
+            "Throughput: 3 B/s",
+            "a: 2 records, 4 bytes",
+            "b: 1 records, 2 bytes",
+        ]
+    );
+}
+
+#[test]
+fn parse_write_policy() {
+    let cli = Cli::try_parse_from(["zsplit", "-", "a", "b"]).unwrap();
+    assert_eq!(cli.write_policy(), WritePolicy::Overwrite);
+
+    let cli = Cli::try_parse_from(["zsplit", "-", "a", "b", "--append"]).unwrap();
+    assert_eq!(cli.write_policy(), WritePolicy::Append);
+
+    let cli = Cli::try_parse_from(["zsplit", "-", "a", "b", "--no-clobber"]).unwrap();
+    assert_eq!(cli.write_policy(), WritePolicy::FailIfExists);
+
+    Cli::try_parse_from(["zsplit", "-", "a", "b", "--append", "--no-clobber"]).unwrap_err();
+    Cli::try_parse_from([
+        "zsplit",
+        "-",
+        "a",
+        "b",
+        "--append",
+        "--json-manifest",
+        "m.json",
+    ])
+    .unwrap_err();
+}
+
+#[test]
+fn parse_rotation() {
+    let cli = Cli::try_parse_from(["zsplit", "-", "a", "b"]).unwrap();
+    assert_eq!(cli.rotation(), None);
+
+    let cli = Cli::try_parse_from(["zsplit", "-", "a", "b", "--rotate-lines", "2"]).unwrap();
+    assert_eq!(cli.rotation(), Some(Rotation::new().max_lines(2)));
+
+    Cli::try_parse_from(["zsplit", "-", "a", "b", "--rotate-bytes", "0"]).unwrap_err();
+    Cli::try_parse_from([
+        "zsplit",
+        "-",
+        "a",
+        "b",
+        "--rotate-lines",
+        "2",
+        "--json-manifest",
+        "manifest.json",
+    ])
+    .unwrap_err();
+}
+
+#[test]
+fn key_of_record() {
+    let mut cli = Cli::try_parse_from(["zsplit", "-", "--output-template", "{key}"]).unwrap();
+    assert_eq!(cli.key_of("alice  42"), "alice");
+    assert_eq!(cli.key_of(""), "");
+
+    cli.key_field = Some(non_zero_usize(2));
+    cli.key_delimiter = Some(',');
+    assert_eq!(cli.key_of("alice,42,x"), "42");
+    assert_eq!(cli.key_of("alice"), "");
+}
+
+#[test]
+fn parse_output_template_conflicts() {
+    Cli::try_parse_from(["zsplit", "-", "a", "b", "--output-template", "{key}"]).unwrap_err();
+    Cli::try_parse_from(["zsplit", "-", "-n", "2", "--output-template", "{key}"]).unwrap_err();
+    Cli::try_parse_from(["zsplit", "-", "--output-template", "out"]).unwrap_err();
+}
+
+#[test]
+fn key_path_of_template() {
+    let cli =
+        Cli::try_parse_from(["zsplit", "-", "--output-template", "by_user/{key}.log"]).unwrap();
+
+    assert_eq!(cli.key_path("a/b"), PathBuf::from("by_user/a_b.log"));
+}
+
+#[test]
+fn fail_on_colliding_key_paths() {
+    let cli = Cli::try_parse_from(["zsplit", "-", "--output-template", "{key}.log"]).unwrap();
+    let options = cli.file_options();
+    let mut opened = BTreeMap::new();
+    opened.insert(PathBuf::from("a_b.log"), "a/b".to_owned());
+
+    let error = cli
+        .open_key_sink("a b", &options, None, &mut opened)
+        .unwrap_err();
+
+    assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+}
+
+#[test]
+fn default_max_keys() {
+    let cli = Cli::try_parse_from(["zsplit", "-", "--output-template", "{key}"]).unwrap();
+    assert_eq!(cli.max_keys(), DEFAULT_MAX_KEYS_UNPOOLED);
+
+    let cli = Cli::try_parse_from([
+        "zsplit",
+        "-",
+        "--output-template",
+        "{key}",
+        "--max-open",
+        "8",
+    ])
+    .unwrap();
+    assert_eq!(cli.max_keys(), DEFAULT_MAX_KEYS_POOLED);
+}
+
+#[test]
+fn parse_error_policy() {
+    let cli = Cli::try_parse_from(["zsplit", "-", "a", "b", "--on-error", "retry"]).unwrap();
+    assert_eq!(cli.error_policy(), ErrorPolicy::Retry(DEFAULT_RETRIES));
+
+    let cli = Cli::try_parse_from(["zsplit", "-", "a", "b", "--on-error", "skip"]).unwrap();
+    assert_eq!(cli.error_policy(), ErrorPolicy::Skip);
+
+    assert_eq!(empty_cli().error_policy(), ErrorPolicy::FailFast);
+
+    Cli::try_parse_from(["zsplit", "-", "a", "b", "--retries", "2"]).unwrap_err();
+}
+
+struct Broken;
+
+impl std::io::Write for Broken {
+    fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn check_dropped_destinations() {
+    let paths = [PathBuf::from("a"), PathBuf::from("b")];
+    let mut source = "0\n1\n".as_bytes();
+    let mut destinations = [
+        Destination::new_with_sink(Box::new(Broken) as Box<dyn std::io::Write>),
+        Destination::new_with_sink(Box::new(std::io::sink())),
+    ];
+
+    let report =
+        zsplit::split_round_robin_with_policy(&mut source, &mut destinations, ErrorPolicy::Skip)
+            .unwrap();
+
+    assert_eq!(
+        *Cli::check_failures(&paths, &report)
+            .unwrap_err()
+            .current_context(),
+        Error::DroppedDestinations { count: 1 }
+    );
+}
+
+#[test]
+fn validate_resumable_policy() {
+    let mut cli = empty_cli();
+    cli.validate_resumable(&cli.destinations).unwrap();
+
+    cli.on_error = Some(OnError::Skip);
+    assert_eq!(
+        *cli.validate_resumable(&cli.destinations)
+            .unwrap_err()
+            .current_context(),
+        Error::Checkpoint
+    );
+}
+
+#[test]
+fn limit_records() {
+    let mut cli = empty_cli();
+    cli.max_record_length = Some(non_zero_usize(2));
+    cli.oversized = Some(Oversized::Truncate);
+    let mut limited = String::new();
+
+    cli.limit_records(Box::new("0123\n4\n".as_bytes()))
+        .read_to_string(&mut limited)
+        .unwrap();
+
+    assert_eq!(limited, "01\n4\n");
+}
+
+#[test]
+fn record_range() {
+    let mut cli = empty_cli();
+    assert_eq!(cli.record_range(), None);
+
+    cli.skip = Some(2);
+    cli.head = Some(3);
+    assert_eq!(cli.record_range(), Some(RecordRange::new(2, Some(5))));
+
+    cli.head = None;
+    assert_eq!(cli.record_range(), Some(RecordRange::new(2, None)));
+}
+
+#[test]
+fn validate_annotation() {
+    let mut cli = empty_cli();
+    cli.annotate = vec![Field::Source, Field::Offset];
+    cli.validate_annotation().unwrap();
+
+    cli.skip = Some(1);
+    assert_eq!(
+        *cli.validate_annotation().unwrap_err().current_context(),
+        Error::UnknownOffsets
+    );
+}
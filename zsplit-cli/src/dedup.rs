@@ -0,0 +1,48 @@
+use std::io::{self, BufRead, Write};
+use zsplit::dedup::{DuplicateFilter, SeenRecords, UniqueRecords};
+use zsplit::destination::Finish;
+
+#[cfg(test)]
+#[path = "./dedup_test.rs"]
+mod dedup_test;
+
+/// The records of all sources, which appeared before, and the sink of their duplicates.
+#[derive(Debug)]
+pub(crate) struct Deduplication<W> {
+    seen: SeenRecords,
+    duplicates: Option<W>,
+}
+
+impl<W: Write> Deduplication<W> {
+    pub(crate) fn new(filter: DuplicateFilter, duplicates: Option<W>) -> Self {
+        Self {
+            seen: SeenRecords::new(filter),
+            duplicates,
+        }
+    }
+
+    /// Finishes the sink of the duplicates, if any.
+    pub(crate) fn finish(self) -> io::Result<()>
+    where
+        W: Finish,
+    {
+        self.duplicates.map_or(Ok(()), Finish::finish)
+    }
+}
+
+/// Drops the records of the `source`, which appeared before in any source, or writes them to the
+/// duplicates, if requested.
+pub(crate) fn unique_records<'a, W: Write>(
+    deduplication: Option<&'a mut Deduplication<W>>,
+    source: impl BufRead + 'a,
+) -> Box<dyn BufRead + 'a> {
+    let Some(deduplication) = deduplication else {
+        return Box::new(source);
+    };
+    let unique = UniqueRecords::new(source, &mut deduplication.seen);
+
+    match &mut deduplication.duplicates {
+        Some(duplicates) => Box::new(unique.duplicates(duplicates)),
+        None => Box::new(unique),
+    }
+}
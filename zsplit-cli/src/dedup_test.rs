@@ -0,0 +1,28 @@
+use super::*;
+use std::io::Read;
+
+#[test]
+fn unique_across_sources() {
+    let mut deduplication = Deduplication::new(DuplicateFilter::Exact, Some(Vec::new()));
+    let mut read = String::new();
+
+    for source in ["a\nb\n", "b\na\nc\n"] {
+        unique_records(Some(&mut deduplication), source.as_bytes())
+            .read_to_string(&mut read)
+            .unwrap();
+    }
+
+    assert_eq!(read, "a\nb\nc\n");
+    assert_eq!(deduplication.duplicates, Some(b"b\na\n".to_vec()));
+}
+
+#[test]
+fn without_deduplication() {
+    let mut read = String::new();
+
+    unique_records::<Vec<u8>>(None, "a\na\n".as_bytes())
+        .read_to_string(&mut read)
+        .unwrap();
+
+    assert_eq!(read, "a\na\n");
+}
@@ -1,46 +1,347 @@
 //! Application for using the [`zsplit`] crate on the CLI.
 
+mod annotation;
+mod checkpoint;
 mod cli;
+mod dedup;
+mod manifest;
+#[cfg(feature = "mmap")]
+mod mapped;
+mod naming;
+mod sink;
 mod source;
 
+use checkpoint::State;
 use clap::Parser;
 use cli::Cli;
+use dedup::Deduplication;
 use error_stack::ResultExt;
 use human_panic::setup_panic;
-use std::io;
+use manifest::Checksummed;
+use sink::SharedSink;
+use source::{Source, SourceChain};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
 use sysexits::ExitCode;
-use zsplit::split_round_robin;
+use zsplit::checkpoint::Checkpoint;
+use zsplit::destination::Finish;
+use zsplit::file::WritePolicy;
+use zsplit::follow::Follow;
+use zsplit::keyed::KeyedDestinations;
+use zsplit::report::SplitReport;
+use zsplit::{split_round_robin_resumable, split_round_robin_with_policy, Destination};
 
 #[derive(thiserror::Error, Debug, PartialEq, Eq)]
 #[allow(missing_docs)]
 pub enum Error {
     #[error("The source is also in destinations")]
     FileDuplicate,
+    #[error("STDIN is used as source multiple times")]
+    StdInDuplicate,
+    #[error("A destination is also written as another output")]
+    OutputDuplicate,
     #[error("The quantity of destinations ({destinations_len}) is smaller as of distributions ({distributions_len})")]
     MoreDistributionsAsDestinations {
         destinations_len: usize,
         distributions_len: usize,
     },
+    #[error("The suffix length ({suffix_length}) is too short for the count of destinations")]
+    SuffixesExhausted { suffix_length: usize },
     #[error("Couldn't read from source")]
     Source,
     #[error("Couldn't write to destination")]
     Destination,
     #[error("Problem occurred during splitting")]
     Split,
+    #[error("Couldn't write the manifest")]
+    Manifest,
+    #[error("Couldn't checkpoint or resume the split")]
+    Checkpoint,
+    #[error("{count} destinations failed and were dropped")]
+    DroppedDestinations { count: usize },
+    #[error("The byte offsets of the records are unknown after skipping or truncating records")]
+    UnknownOffsets,
 }
 
 type Result<T> = error_stack::Result<T, Error>;
 
 fn try_main() -> Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+
+    cli.expand_sources().attach(ExitCode::NoInput)?;
+    let sources = cli.sources().attach(ExitCode::NoInput)?;
 
     cli.validate().attach(ExitCode::Usage)?;
 
-    let mut source = cli.source.reading_buffer().change_context(Error::Source)?;
+    if cli.checkpoint.is_some() {
+        return split_resumable(&cli, &sources);
+    }
+
+    if cli.follow {
+        return split_follow(&cli, &sources);
+    }
+
+    if cli.contiguous {
+        return split_contiguous(&cli, &sources);
+    }
+
+    let readers = sources
+        .iter()
+        .map(|source| {
+            source.reading_buffer().map(|reader| {
+                Checksummed::new(cli.limit_records(reader), cli.json_manifest.is_some())
+            })
+        })
+        .collect::<error_stack::Result<Vec<_>, io::Error>>()
+        .change_context(Error::Source)?;
+
+    if cli.output_template.is_some() {
+        return split_by_key(&cli, &sources, readers);
+    }
 
     let mut destinations = cli.destinations()?;
+    let paths = cli.destination_paths()?;
+    let mut deduplication = cli.deduplication()?;
+
+    let (readers, report) = split(&cli, readers, deduplication.as_mut(), |reader| {
+        split_round_robin_with_policy(reader, &mut destinations, cli.error_policy())
+    })
+    .map_err(|error| split_error(error, &paths))?;
+    cli.finish_deduplication(deduplication)?;
+
+    complete(&cli, &sources, &readers, &paths, destinations, &report)
+}
+
+/// Splits the readers into destinations, which are created for the keys of the records.
+fn split_by_key<R: BufRead>(
+    cli: &Cli,
+    sources: &[Source],
+    readers: Vec<Checksummed<R>>,
+) -> Result<()> {
+    let options = cli.file_options();
+    let pool = cli.file_pool(options);
+    let mut destinations = KeyedDestinations::new()
+        .max_keys(cli.max_keys())
+        .flush_policy(cli.flush_policy());
+    if let Some(annotation) = cli.annotation()? {
+        destinations = destinations.transform(annotation);
+    }
+
+    let mut deduplication = cli.deduplication()?;
+    let mut opened = BTreeMap::new();
+
+    let result = split(cli, readers, deduplication.as_mut(), |reader| {
+        zsplit::split_by_key(
+            reader,
+            &mut destinations,
+            |line| cli.key_of(line),
+            |key| cli.open_key_sink(key, &options, pool.as_ref(), &mut opened),
+        )
+    });
+
+    let paths = destinations
+        .keys()
+        .map(|key| cli.key_path(key))
+        .collect::<Vec<_>>();
+    let (readers, report) = result.map_err(|error| split_error(error, &paths))?;
+    cli.finish_deduplication(deduplication)?;
+
+    let destinations = destinations
+        .into_inner()
+        .into_iter()
+        .map(|(_, destination)| destination)
+        .collect();
+
+    complete(cli, sources, &readers, &paths, destinations, &report)
+}
+
+/// Splits the only source file with checkpoints, from which a later call with `--resume`
+/// continues.
+fn split_resumable(cli: &Cli, sources: &[Source]) -> Result<()> {
+    let (Some(state_file), [Source::PathBuf(source)]) = (&cli.checkpoint, sources) else {
+        return Err(error_stack::Report::new(Error::Checkpoint)
+            .attach_printable("Only a single source file can be resumed")
+            .attach(ExitCode::Usage));
+    };
+    let paths = cli.destination_paths()?;
+    cli.validate_resumable(&paths)?;
+
+    let state = cli.resume_state(source, &paths)?;
+    let (checkpoint, options) = match &state {
+        Some(state) => {
+            state
+                .truncate_destinations()
+                .change_context(Error::Checkpoint)?;
+            (
+                state.checkpoint(),
+                cli.file_options().policy(WritePolicy::Append),
+            )
+        }
+        None => (Checkpoint::default(), cli.file_options()),
+    };
+
+    let mut destinations = cli.destinations_with(options)?;
+    let distributions = cli.destination_distributions(paths.len());
+    let mut reader = checkpoint::open_source(source, checkpoint.offset)
+        .change_context(Error::Source)
+        .attach_printable_lazy(|| format!("Couldn't resume file `{}`", source.display()))?;
+
+    let report = split_round_robin_resumable(
+        &mut reader,
+        &mut destinations,
+        cli.error_policy(),
+        &checkpoint,
+        cli.checkpoint_interval(),
+        |checkpoint| {
+            State::new(
+                source,
+                cli.line_factor.get(),
+                &distributions,
+                &paths,
+                checkpoint,
+            )
+            .save(state_file)
+        },
+    )
+    .map_err(|error| split_error(error, &paths))?;
+
+    complete::<io::Empty, _>(cli, sources, &[], &paths, destinations, &report)?;
+
+    std::fs::remove_file(state_file).change_context(Error::Checkpoint)
+}
+
+/// Splits the only source file, while it keeps growing, until an error occurs.
+fn split_follow(cli: &Cli, sources: &[Source]) -> Result<()> {
+    let [Source::PathBuf(source)] = sources else {
+        return Err(error_stack::Report::new(Error::Source)
+            .attach_printable("Only a single source file can be followed")
+            .attach(ExitCode::Usage));
+    };
+    let paths = cli.destination_paths()?;
+
+    let mut destinations = cli
+        .destinations()?
+        .into_iter()
+        .map(|destination| {
+            Destination::new(
+                SharedSink::new(destination.sink),
+                destination.assigned_lines,
+            )
+        })
+        .collect::<Vec<_>>();
+    let sinks = destinations
+        .iter()
+        .map(|destination| destination.sink.clone())
+        .collect::<Vec<_>>();
+
+    let reader = Follow::open(source)
+        .change_context(Error::Source)
+        .attach_printable_lazy(|| format!("Couldn't follow file `{}`", source.display()))?
+        .on_idle(move || sinks.iter().try_for_each(SharedSink::flush_healthy));
+    let mut deduplication = cli.deduplication()?;
+    let mut reader = cli.select_records(dedup::unique_records(
+        deduplication.as_mut(),
+        cli.limit_records(Box::new(reader)),
+    ));
+
+    let report = split_round_robin_with_policy(&mut reader, &mut destinations, cli.error_policy())
+        .map_err(|error| split_error(error, &paths))?;
+
+    Cli::check_failures(&paths, &report)
+}
+
+/// Splits the only source file into contiguous chunks, which are written in parallel.
+fn split_contiguous(cli: &Cli, sources: &[Source]) -> Result<()> {
+    let [Source::PathBuf(source)] = sources else {
+        return Err(error_stack::Report::new(Error::Source)
+            .attach_printable("Only a single source file can be split into contiguous chunks")
+            .attach(ExitCode::Usage));
+    };
+    source::open_uncompressed(source)
+        .change_context(Error::Source)
+        .attach_printable_lazy(|| format!("Couldn't seek file `{}`", source.display()))?;
+    let paths = cli.destination_paths()?;
+
+    let mut destinations = cli.contiguous_destinations()?;
+
+    let report = zsplit::split_contiguous(|| source::open_uncompressed(source), &mut destinations)
+        .map_err(|error| split_error(error, &paths))?;
+
+    complete::<io::Empty, _>(cli, sources, &[], &paths, destinations, &report)
+}
+
+/// Splits the readers with `split`, returning the exhausted readers and the report.
+///
+/// The records of all readers are deduplicated by the `deduplication`, if any.
+fn split<R: BufRead>(
+    cli: &Cli,
+    mut readers: Vec<R>,
+    mut deduplication: Option<&mut Deduplication<impl Write>>,
+    mut split: impl FnMut(&mut dyn BufRead) -> zsplit::Result<SplitReport>,
+) -> zsplit::Result<(Vec<R>, SplitReport)> {
+    if cli.restart_per_source {
+        let mut report = SplitReport::default();
+        for (index, reader) in readers.iter_mut().enumerate() {
+            cli.enter_source(index);
+            let reader = dedup::unique_records(deduplication.as_deref_mut(), reader);
+            report.merge(&split(&mut cli.select_records(reader))?);
+        }
+        return Ok((readers, report));
+    }
+
+    let mut chain = cli.track_sources(SourceChain::new(readers));
+    let reader = dedup::unique_records(deduplication, &mut chain);
+    let report = split(&mut cli.select_records(reader))?;
+    Ok((chain.into_sources(), report))
+}
+
+/// Converts the `error` of a split into a report, which names the path of the affected
+/// destination.
+fn split_error(error: zsplit::Error, paths: &[PathBuf]) -> error_stack::Report<Error> {
+    let path = error
+        .destination()
+        .and_then(|index| paths.get(index))
+        .map(|path| format!("Destination `{}`", path.display()));
+    let exit_code = ExitCode::from(error.kind());
+
+    let report = error_stack::Report::new(error)
+        .change_context(Error::Split)
+        .attach(exit_code);
+    match path {
+        Some(path) => report.attach_printable(path),
+        None => report,
+    }
+}
+
+/// Finishes the destinations at `paths`, which weren't dropped, and writes the requested manifests
+/// and statistics.
+fn complete<R: BufRead, W: Finish>(
+    cli: &Cli,
+    sources: &[Source],
+    readers: &[Checksummed<R>],
+    paths: &[PathBuf],
+    destinations: Vec<Destination<Checksummed<W>>>,
+    report: &SplitReport,
+) -> Result<()> {
+    let json_manifest = cli.json_manifest(sources, readers, paths, &destinations);
+
+    let (healthy_paths, healthy): (Vec<_>, Vec<_>) = paths
+        .iter()
+        .cloned()
+        .zip(destinations)
+        .enumerate()
+        .filter_map(|(index, destination)| (!report.is_failed(index)).then_some(destination))
+        .unzip();
+    Cli::finish_destinations(&healthy_paths, healthy)?;
+
+    cli.write_manifest()?;
+
+    cli.write_json_manifest(json_manifest)?;
+
+    cli.print_stats(paths, report);
 
-    split_round_robin(&mut source, &mut destinations).change_context(Error::Split)
+    Cli::check_failures(paths, report)
 }
 
 fn main() -> std::process::ExitCode {
@@ -0,0 +1,178 @@
+use crate::source::Source;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::{self, BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+use zsplit::destination::Finish;
+use zsplit::file::Staged;
+
+#[cfg(test)]
+#[path = "./manifest_test.rs"]
+mod manifest_test;
+
+/// The machine-readable summary of a split operation.
+///
+/// Sizes, line counts and checksums describe the uncompressed content.
+#[derive(Serialize, Debug)]
+pub(crate) struct Manifest {
+    pub(crate) sources: Vec<SourceEntry>,
+    pub(crate) strategy: &'static str,
+    pub(crate) restart_per_source: bool,
+    pub(crate) line_factor: usize,
+    pub(crate) distributions: Vec<usize>,
+    pub(crate) destinations: Vec<DestinationEntry>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub(crate) struct SourceEntry {
+    pub(crate) path: PathBuf,
+    pub(crate) size: u64,
+    pub(crate) sha256: String,
+}
+
+impl SourceEntry {
+    pub(crate) fn new(source: &Source, checksum: Checksum) -> Self {
+        let path = match source {
+            Source::PathBuf(path) => path.clone(),
+            Source::Glob(pattern) => pattern.into(),
+            Source::StdIn => "-".into(),
+        };
+
+        Self {
+            path,
+            size: checksum.bytes,
+            sha256: checksum.hex_digest(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub(crate) struct DestinationEntry {
+    pub(crate) path: PathBuf,
+    pub(crate) lines: u64,
+    pub(crate) bytes: u64,
+    pub(crate) sha256: String,
+}
+
+impl DestinationEntry {
+    pub(crate) fn new(path: &Path, checksum: Checksum) -> Self {
+        Self {
+            path: path.to_owned(),
+            lines: checksum.lines,
+            bytes: checksum.bytes,
+            sha256: checksum.hex_digest(),
+        }
+    }
+}
+
+impl Manifest {
+    pub(crate) fn write(&self, path: &Path) -> io::Result<()> {
+        let mut file = io::BufWriter::new(std::fs::File::create(path)?);
+
+        serde_json::to_writer_pretty(&mut file, self)?;
+        file.write_all(b"\n")?;
+
+        file.flush()
+    }
+}
+
+/// Counts and hashes the content passing through.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Checksum {
+    lines: u64,
+    bytes: u64,
+    hasher: Sha256,
+}
+
+impl Checksum {
+    fn update(&mut self, data: &[u8]) {
+        self.lines += to_u64(memchr::memchr_iter(b'\n', data).count());
+        self.bytes += to_u64(data.len());
+        self.hasher.update(data);
+    }
+
+    fn hex_digest(self) -> String {
+        self.hasher
+            .finalize()
+            .iter()
+            .flat_map(|byte| [byte >> 4, byte & 0x0f])
+            .filter_map(|nibble| char::from_digit(u32::from(nibble), 16))
+            .collect()
+    }
+}
+
+fn to_u64(value: usize) -> u64 {
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
+
+/// A reader or writer, which optionally [`Checksum`]s its content.
+#[derive(Debug)]
+pub(crate) struct Checksummed<I> {
+    inner: I,
+    checksum: Option<Checksum>,
+}
+
+impl<I> Checksummed<I> {
+    pub(crate) fn new(inner: I, enabled: bool) -> Self {
+        Self {
+            inner,
+            checksum: enabled.then(Checksum::default),
+        }
+    }
+
+    /// The [`Checksum`] of the content so far, if it is enabled.
+    pub(crate) fn checksum(&self) -> Option<Checksum> {
+        self.checksum.clone()
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        if let Some(checksum) = &mut self.checksum {
+            checksum.update(data);
+        }
+    }
+}
+
+impl<R: BufRead> Read for Checksummed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.update(&buf[..len]);
+        Ok(len)
+    }
+}
+
+impl<R: BufRead> BufRead for Checksummed<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if let Some(checksum) = &mut self.checksum {
+            if let Ok(buffer) = self.inner.fill_buf() {
+                checksum.update(&buffer[..amt.min(buffer.len())]);
+            }
+        }
+        self.inner.consume(amt);
+    }
+}
+
+impl<W: Write> Write for Checksummed<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.update(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Finish> Finish for Checksummed<W> {
+    fn finish(self) -> io::Result<()> {
+        self.inner.finish()
+    }
+
+    fn finish_staged(self) -> io::Result<Staged> {
+        self.inner.finish_staged()
+    }
+}
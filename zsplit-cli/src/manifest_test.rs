@@ -0,0 +1,52 @@
+use super::*;
+
+const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+const ABC_SHA256: &str = "edeaaff3f1774ad2888673770c6d64097e391bc362d7d6fb34982ddf0efd18cb";
+
+#[test]
+fn checksum_empty() {
+    assert_eq!(Checksum::default().hex_digest(), EMPTY_SHA256);
+}
+
+#[test]
+fn checksum_written() {
+    let mut writer = Checksummed::new(Vec::new(), true);
+    writer.write_all(b"abc\n").unwrap();
+
+    let checksum = writer.checksum().unwrap();
+    assert_eq!(checksum.lines, 1);
+    assert_eq!(checksum.bytes, 4);
+    assert_eq!(checksum.hex_digest(), ABC_SHA256);
+}
+
+#[test]
+fn checksum_read_by_lines() {
+    let mut reader = Checksummed::new(&b"abc\n"[..], true);
+    reader.by_ref().lines().for_each(drop);
+
+    let checksum = reader.checksum().unwrap();
+    assert_eq!(checksum.lines, 1);
+    assert_eq!(checksum.hex_digest(), ABC_SHA256);
+}
+
+#[test]
+fn checksum_disabled() {
+    let mut writer = Checksummed::new(Vec::new(), false);
+    writer.write_all(b"abc\n").unwrap();
+
+    assert!(writer.checksum().is_none());
+}
+
+#[test]
+fn source_entry_stdin() {
+    let entry = SourceEntry::new(&Source::StdIn, Checksum::default());
+
+    assert_eq!(
+        entry,
+        SourceEntry {
+            path: PathBuf::from("-"),
+            size: 0,
+            sha256: EMPTY_SHA256.to_owned(),
+        }
+    );
+}
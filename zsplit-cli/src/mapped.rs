@@ -0,0 +1,81 @@
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+
+#[cfg(test)]
+#[path = "./mapped_test.rs"]
+mod mapped_test;
+
+/// A regular file mapped into memory, whose records are found directly in the mapping.
+///
+/// The whole file is a single buffer, so the records are written to the destinations without
+/// copying them first.
+#[derive(Debug)]
+pub(crate) struct MappedFile {
+    map: Mmap,
+    position: usize,
+}
+
+impl MappedFile {
+    /// Maps the `file`, if it is a regular, non-empty file.
+    ///
+    /// Other files and failing mappings return `None`, so they are read by a buffer instead.
+    pub(crate) fn map(file: &File) -> Option<Self> {
+        let metadata = file.metadata().ok()?;
+        if !metadata.is_file() || metadata.len() == 0 {
+            return None;
+        }
+
+        // SAFETY: The mapping is only read and never outlives `MappedFile`. Modifying the file
+        // during the split is undefined behaviour, which is documented for the `mmap` feature.
+        #[allow(unsafe_code)]
+        let map = unsafe { Mmap::map(file) }.ok()?;
+
+        Some(Self { map, position: 0 })
+    }
+}
+
+impl Read for MappedFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl BufRead for MappedFile {
+    #[inline]
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.map.get(self.position..).unwrap_or_default())
+    }
+
+    #[inline]
+    fn consume(&mut self, amt: usize) {
+        self.position = (self.position + amt).min(self.map.len());
+    }
+}
+
+impl Seek for MappedFile {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match position {
+            SeekFrom::Start(offset) => (0, i128::from(offset)),
+            SeekFrom::End(offset) => (self.map.len(), i128::from(offset)),
+            SeekFrom::Current(offset) => (self.position, i128::from(offset)),
+        };
+
+        self.position = i128::try_from(base)
+            .ok()
+            .and_then(|base| base.checked_add(offset))
+            .and_then(|position| usize::try_from(position).ok())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Can't seek before the start of the mapped file",
+                )
+            })?;
+        Ok(u64::try_from(self.position).unwrap_or(u64::MAX))
+    }
+}
@@ -0,0 +1,55 @@
+use super::*;
+use assert_fs::prelude::*;
+use assert_fs::NamedTempFile;
+
+#[test]
+fn read_mapped_lines() {
+    let file = NamedTempFile::new("mapped").unwrap();
+    file.write_str("0\n1\n2").unwrap();
+
+    let mapped = MappedFile::map(&File::open(&file).unwrap()).unwrap();
+
+    assert_eq!(
+        mapped.lines().collect::<io::Result<Vec<_>>>().unwrap(),
+        ["0", "1", "2"]
+    );
+}
+
+#[test]
+fn consume_beyond_end() {
+    let file = NamedTempFile::new("mapped_consume").unwrap();
+    file.write_str("0\n").unwrap();
+    let mut mapped = MappedFile::map(&File::open(&file).unwrap()).unwrap();
+
+    mapped.consume(3);
+
+    assert!(mapped.fill_buf().unwrap().is_empty());
+}
+
+#[test]
+fn seek_mapped() {
+    let file = NamedTempFile::new("mapped_seek").unwrap();
+    file.write_str("0\n1\n").unwrap();
+    let mut mapped = MappedFile::map(&File::open(&file).unwrap()).unwrap();
+
+    assert_eq!(mapped.seek(SeekFrom::End(-2)).unwrap(), 2);
+    assert_eq!(mapped.fill_buf().unwrap(), b"1\n");
+    assert_eq!(mapped.seek(SeekFrom::Current(3)).unwrap(), 5);
+    assert!(mapped.fill_buf().unwrap().is_empty());
+    mapped.seek(SeekFrom::Start(0)).unwrap();
+    assert!(mapped.seek(SeekFrom::Current(-1)).is_err());
+}
+
+#[test]
+fn empty_file_isnt_mapped() {
+    let file = NamedTempFile::new("mapped_empty").unwrap();
+    file.touch().unwrap();
+
+    assert!(MappedFile::map(&File::open(&file).unwrap()).is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn device_isnt_mapped() {
+    assert!(MappedFile::map(&File::open("/dev/null").unwrap()).is_none());
+}
@@ -0,0 +1,135 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[cfg(test)]
+#[path = "./naming_test.rs"]
+mod naming_test;
+
+const INDEX_PLACEHOLDER: &str = "{index";
+const KEY_PLACEHOLDER: &str = "{key}";
+
+/// Generates the suffix of the destination with `index` like `split` from coreutils.
+///
+/// Returns `None` when `length` is too short for `index`.
+pub(crate) fn suffix(index: usize, length: usize, numeric: bool) -> Option<String> {
+    let (radix, first_digit) = if numeric { (10, b'0') } else { (26, b'a') };
+
+    let mut remaining = index;
+    let mut suffix = vec![first_digit; length];
+    for digit in suffix.iter_mut().rev() {
+        *digit = first_digit + u8::try_from(remaining % radix).ok()?;
+        remaining /= radix;
+    }
+
+    if remaining != 0 {
+        return None;
+    }
+    String::from_utf8(suffix).ok()
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub(crate) enum TemplateError {
+    #[error("The template has no `{{index}}` placeholder")]
+    MissingIndex,
+    #[error("The template has no `{{key}}` placeholder")]
+    MissingKey,
+    #[error("The placeholder has to be `{{index}}` or `{{index:0WIDTH}}`")]
+    InvalidPlaceholder,
+}
+
+/// A destination path with an `{index}` or zero padded `{index:03}` placeholder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Template {
+    before: String,
+    width: usize,
+    after: String,
+}
+
+impl Template {
+    pub(crate) fn path(&self, index: usize) -> PathBuf {
+        format!(
+            "{}{index:0width$}{}",
+            self.before,
+            self.after,
+            width = self.width
+        )
+        .into()
+    }
+}
+
+impl FromStr for Template {
+    type Err = TemplateError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let (before, placeholder) = template
+            .split_once(INDEX_PLACEHOLDER)
+            .ok_or(TemplateError::MissingIndex)?;
+        let (format, after) = placeholder
+            .split_once('}')
+            .ok_or(TemplateError::InvalidPlaceholder)?;
+
+        Ok(Self {
+            before: before.to_owned(),
+            width: parse_width(format)?,
+            after: after.to_owned(),
+        })
+    }
+}
+
+fn parse_width(format: &str) -> Result<usize, TemplateError> {
+    if format.is_empty() {
+        return Ok(0);
+    }
+
+    format
+        .strip_prefix(":0")
+        .and_then(|width| width.parse().ok())
+        .ok_or(TemplateError::InvalidPlaceholder)
+}
+
+/// A destination path with a `{key}` placeholder for the key of a record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct KeyTemplate {
+    before: String,
+    after: String,
+}
+
+impl KeyTemplate {
+    /// The path of `key`, which is sanitized to stay a single path component.
+    pub(crate) fn path(&self, key: &str) -> PathBuf {
+        format!("{}{}{}", self.before, sanitize_key(key), self.after).into()
+    }
+}
+
+impl FromStr for KeyTemplate {
+    type Err = TemplateError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let (before, after) = template
+            .split_once(KEY_PLACEHOLDER)
+            .ok_or(TemplateError::MissingKey)?;
+
+        Ok(Self {
+            before: before.to_owned(),
+            after: after.to_owned(),
+        })
+    }
+}
+
+/// Replaces every character except ASCII alphanumerics, `-`, `_` and `.` with `_`.
+///
+/// An empty key and the relative path components `.` and `..` are prefixed with `_`.
+fn sanitize_key(key: &str) -> String {
+    let key: String = key
+        .chars()
+        .map(|character| match character {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => character,
+            _ => '_',
+        })
+        .collect();
+
+    match key.as_str() {
+        "" | "." | ".." => format!("_{key}"),
+        _ => key,
+    }
+}
@@ -0,0 +1,84 @@
+use super::*;
+
+#[test]
+fn alphabetic_suffixes() {
+    assert_eq!(suffix(0, 2, false).unwrap(), "aa");
+    assert_eq!(suffix(1, 2, false).unwrap(), "ab");
+    assert_eq!(suffix(26, 2, false).unwrap(), "ba");
+    assert_eq!(suffix(675, 2, false).unwrap(), "zz");
+}
+
+#[test]
+fn numeric_suffixes() {
+    assert_eq!(suffix(0, 2, true).unwrap(), "00");
+    assert_eq!(suffix(42, 3, true).unwrap(), "042");
+}
+
+#[test]
+fn exhausted_suffixes() {
+    assert_eq!(suffix(676, 2, false), None);
+    assert_eq!(suffix(10, 1, true), None);
+}
+
+#[test]
+fn template_without_width() {
+    let template: Template = "out_{index}.txt".parse().unwrap();
+
+    assert_eq!(template.path(7), PathBuf::from("out_7.txt"));
+}
+
+#[test]
+fn template_with_width() {
+    let template: Template = "shards/{index:03}".parse().unwrap();
+
+    assert_eq!(template.path(7), PathBuf::from("shards/007"));
+    assert_eq!(template.path(1234), PathBuf::from("shards/1234"));
+}
+
+#[test]
+fn template_without_index() {
+    assert_eq!(
+        "out.txt".parse::<Template>().unwrap_err(),
+        TemplateError::MissingIndex
+    );
+}
+
+#[test]
+fn template_with_invalid_placeholder() {
+    assert_eq!(
+        "out_{index:x}.txt".parse::<Template>().unwrap_err(),
+        TemplateError::InvalidPlaceholder
+    );
+    assert_eq!(
+        "out_{index".parse::<Template>().unwrap_err(),
+        TemplateError::InvalidPlaceholder
+    );
+}
+
+#[test]
+fn key_template_path() {
+    let template: KeyTemplate = "by_user/{key}.log".parse().unwrap();
+
+    assert_eq!(template.path("alice"), PathBuf::from("by_user/alice.log"));
+}
+
+#[test]
+fn key_template_sanitizes_key() {
+    let template: KeyTemplate = "{key}".parse().unwrap();
+
+    assert_eq!(
+        template.path("../etc/passwd"),
+        PathBuf::from(".._etc_passwd")
+    );
+    assert_eq!(template.path(".."), PathBuf::from("_.."));
+    assert_eq!(template.path(""), PathBuf::from("_"));
+    assert_eq!(template.path("a b/ä"), PathBuf::from("a_b__"));
+}
+
+#[test]
+fn key_template_without_placeholder() {
+    assert_eq!(
+        "out_{index}.txt".parse::<KeyTemplate>(),
+        Err(TemplateError::MissingKey)
+    );
+}
@@ -0,0 +1,106 @@
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+use zsplit::destination::Finish;
+use zsplit::file::Staged;
+use zsplit::pool::PooledFile;
+
+#[cfg(test)]
+#[path = "./sink_test.rs"]
+mod sink_test;
+
+/// The sink of a destination, which is either opened directly or lazily by a pool.
+#[derive(Debug)]
+pub(crate) enum DestinationSink<S> {
+    Direct(S),
+    Pooled(PooledFile),
+}
+
+impl<S: Write> Write for DestinationSink<S> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Direct(sink) => sink.write(buf),
+            Self::Pooled(sink) => sink.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Direct(sink) => sink.flush(),
+            Self::Pooled(sink) => sink.flush(),
+        }
+    }
+}
+
+impl<S: Finish> Finish for DestinationSink<S> {
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Self::Direct(sink) => sink.finish(),
+            Self::Pooled(sink) => sink.finish(),
+        }
+    }
+
+    fn finish_staged(self) -> io::Result<Staged> {
+        match self {
+            Self::Direct(sink) => sink.finish_staged(),
+            Self::Pooled(sink) => sink.finish_staged(),
+        }
+    }
+}
+
+/// A sink shared with the idle handler of `--follow`, which flushes it while waiting for new
+/// records.
+#[derive(Debug)]
+pub(crate) struct SharedSink<S>(Rc<RefCell<Shared<S>>>);
+
+#[derive(Debug)]
+struct Shared<S> {
+    sink: S,
+    failed: bool,
+}
+
+impl<S: Write> SharedSink<S> {
+    pub(crate) fn new(sink: S) -> Self {
+        Self(Rc::new(RefCell::new(Shared {
+            sink,
+            failed: false,
+        })))
+    }
+
+    /// Flushes the sink, unless its last operation failed, so a dropped destination isn't touched
+    /// again.
+    pub(crate) fn flush_healthy(&self) -> io::Result<()> {
+        let mut shared = self.0.borrow_mut();
+
+        if shared.failed {
+            return Ok(());
+        }
+        shared.track(Write::flush)
+    }
+}
+
+impl<S: Write> Shared<S> {
+    fn track<T>(&mut self, operation: impl FnOnce(&mut S) -> io::Result<T>) -> io::Result<T> {
+        let result = operation(&mut self.sink);
+        self.failed = result.is_err();
+        result
+    }
+}
+
+impl<S> Clone for SharedSink<S> {
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0))
+    }
+}
+
+impl<S: Write> Write for SharedSink<S> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().track(|sink| sink.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.borrow_mut().track(Write::flush)
+    }
+}
@@ -0,0 +1,53 @@
+use super::*;
+
+/// A sink, which fails after `capacity` bytes.
+#[derive(Debug, Default)]
+struct Limited {
+    written: Vec<u8>,
+    capacity: usize,
+    flushes: usize,
+}
+
+impl Write for Limited {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written.len() + buf.len() > self.capacity {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flushes += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn shared_sink_writes_into_clones() {
+    let mut sink = SharedSink::new(Limited {
+        capacity: 4,
+        ..Limited::default()
+    });
+    let clone = sink.clone();
+
+    sink.write_all(b"ab").unwrap();
+    clone.flush_healthy().unwrap();
+
+    let shared = clone.0.borrow();
+    assert_eq!(shared.sink.written, b"ab");
+    assert_eq!(shared.sink.flushes, 1);
+}
+
+#[test]
+fn shared_sink_skips_failed_flush() {
+    let mut sink = SharedSink::new(Limited::default());
+
+    assert!(sink.write_all(b"ab").is_err());
+    sink.flush_healthy().unwrap();
+    assert_eq!(sink.0.borrow().sink.flushes, 0);
+
+    sink.flush().unwrap();
+    sink.flush_healthy().unwrap();
+    assert_eq!(sink.0.borrow().sink.flushes, 2);
+}
@@ -1,41 +1,361 @@
+use bool_ext::BoolExt;
 use error_stack::{Result, ResultExt};
-use io::{BufRead, BufReader};
+use io::{BufRead, BufReader, Read, Seek};
 use std::fs::File;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use zsplit::compression::{decompress, Compression};
 
 #[cfg(test)]
 #[path = "./source_test.rs"]
 mod source_test;
 
-#[derive(Debug, PartialEq, Eq)]
+/// The capacity of the buffer of a source file, which bounds the records written at once.
+const BUFFER_CAPACITY: usize = 128 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum Source {
     PathBuf(PathBuf),
+    Glob(String),
     StdIn,
 }
 
 impl Source {
+    /// Opens the source and decompresses it, when it starts with the magic bytes of an enabled
+    /// compression format.
     pub(crate) fn reading_buffer(&self) -> Result<Box<dyn BufRead>, io::Error> {
         match self {
-            Self::PathBuf(current_file) => Ok(Box::new(BufReader::new(
-                File::open(current_file).attach_printable_lazy(|| {
-                    format!(
-                        "Couldn't open file `{}` as readable",
-                        current_file.display()
-                    )
-                })?,
-            ))),
+            Self::PathBuf(current_file) => open_file(current_file),
+            Self::Glob(pattern) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The glob pattern `{pattern}` has to be expanded before reading"),
+            )
+            .into()),
             Self::StdIn => {
                 let stdin = Box::leak(Box::new(io::stdin()));
-                Ok(Box::new(stdin.lock()))
+                decompress(stdin.lock())
+                    .attach_printable("Couldn't detect the compression of STDIN")
             }
         }
     }
 
+    /// Expands a glob pattern into the matching files in [natural order](natural_order).
+    ///
+    /// Every other source is returned as is.
+    pub(crate) fn expand(&self) -> crate::Result<Vec<Self>> {
+        let Self::Glob(pattern) = self else {
+            return Ok(vec![self.clone()]);
+        };
+
+        let mut paths = glob::glob(pattern)
+            .change_context(crate::Error::Source)
+            .attach_printable_lazy(|| format!("Invalid glob pattern `{pattern}`"))?
+            .map(|path| path.change_context(crate::Error::Source))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        (!paths.is_empty())
+            .err(crate::Error::Source)
+            .attach_printable_lazy(|| format!("No file matches the glob pattern `{pattern}`"))?;
+
+        paths.sort_by(|left, right| natural_order(left, right));
+        Ok(paths.into_iter().map(Self::PathBuf).collect())
+    }
+
+    /// The source of a command line argument.
+    ///
+    /// An existing file is taken literally, even if its path looks like a glob pattern.
     pub(crate) fn from_os_str(path: &std::ffi::OsStr) -> Self {
         if path == "-" {
             return Self::StdIn;
         }
+        let pattern = path
+            .to_str()
+            .filter(|path| is_glob_pattern(path) && !Path::new(path).exists());
+        if let Some(pattern) = pattern {
+            return Self::Glob(pattern.to_owned());
+        }
         Self::PathBuf((*path).into())
     }
 }
+
+fn is_glob_pattern(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// Compares the paths alphabetically, but their numbers by value, so 'app.log.2' precedes
+/// 'app.log.10'.
+fn natural_order(left: &Path, right: &Path) -> std::cmp::Ordering {
+    let (left, right) = (left.to_string_lossy(), right.to_string_lossy());
+
+    chunks(&left).cmp(chunks(&right))
+}
+
+/// A part of a path, which is compared as a whole.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Chunk<'a> {
+    /// Digits without leading zeros, which are compared by their count first.
+    Number(usize, &'a str),
+    Text(&'a str),
+}
+
+/// Splits the `path` into alternating chunks of digits and other characters.
+fn chunks(path: &str) -> impl Iterator<Item = Chunk<'_>> {
+    let mut rest = path;
+
+    std::iter::from_fn(move || {
+        let digits = rest.chars().next()?.is_ascii_digit();
+        let len = rest
+            .find(|character: char| character.is_ascii_digit() != digits)
+            .unwrap_or(rest.len());
+        let (chunk, tail) = rest.split_at(len);
+        rest = tail;
+
+        Some(if digits {
+            let number = chunk.trim_start_matches('0');
+            Chunk::Number(number.len(), number)
+        } else {
+            Chunk::Text(chunk)
+        })
+    })
+}
+
+/// Opens the file at `path` and decompresses it, when it starts with the magic bytes of an enabled
+/// compression format.
+///
+/// A regular file is mapped into memory with the `mmap` feature.
+fn open_file(path: &Path) -> Result<Box<dyn BufRead>, io::Error> {
+    let file = File::open(path)
+        .attach_printable_lazy(|| format!("Couldn't open file `{}` as readable", path.display()))?;
+
+    #[cfg(feature = "mmap")]
+    if let Some(mapped) = crate::mapped::MappedFile::map(&file) {
+        return decompress(mapped).attach_printable_lazy(|| compression_error(path));
+    }
+
+    decompress(BufReader::with_capacity(BUFFER_CAPACITY, file))
+        .attach_printable_lazy(|| compression_error(path))
+}
+
+fn compression_error(path: &Path) -> String {
+    format!(
+        "Couldn't detect the compression of file `{}`",
+        path.display()
+    )
+}
+
+/// A source file, which is read by a buffer and could be sought.
+pub(crate) trait SeekableSource: BufRead + Seek {}
+
+impl<R: BufRead + Seek> SeekableSource for R {}
+
+/// Opens the file at `path` for seeking, which requires it to be uncompressed.
+///
+/// A regular file is mapped into memory with the `mmap` feature.
+pub(crate) fn open_uncompressed(path: &Path) -> io::Result<Box<dyn SeekableSource>> {
+    let file = File::open(path)?;
+
+    #[cfg(feature = "mmap")]
+    if let Some(mapped) = crate::mapped::MappedFile::map(&file) {
+        return uncompressed(mapped);
+    }
+
+    uncompressed(BufReader::with_capacity(BUFFER_CAPACITY, file))
+}
+
+/// Fails, when the `reader` starts with the magic bytes of an enabled compression format.
+fn uncompressed<R: SeekableSource + 'static>(mut reader: R) -> io::Result<Box<dyn SeekableSource>> {
+    if Compression::from_magic_bytes(&mut reader)? != Compression::None {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "A compressed source can't be split into contiguous chunks",
+        ));
+    }
+    Ok(Box::new(reader))
+}
+
+/// The source, whose records are read, and where it starts in the read stream.
+#[derive(Debug, Default)]
+pub(crate) struct SourcePosition {
+    index: AtomicUsize,
+    /// The count of records before the source.
+    record: AtomicU64,
+    /// The count of bytes before the source.
+    offset: AtomicU64,
+}
+
+impl SourcePosition {
+    /// Enters the source at `index`, which starts after `record` records and `offset` bytes.
+    pub(crate) fn enter(&self, index: usize, record: u64, offset: u64) {
+        self.index.store(index, Ordering::Relaxed);
+        self.record.store(record, Ordering::Relaxed);
+        self.offset.store(offset, Ordering::Relaxed);
+    }
+
+    pub(crate) fn index(&self) -> usize {
+        self.index.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record(&self) -> u64 {
+        self.record.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn offset(&self) -> u64 {
+        self.offset.load(Ordering::Relaxed)
+    }
+}
+
+/// Counts the records and bytes, which were read from a [`SourceChain`].
+#[derive(Debug)]
+struct Tracking {
+    position: Arc<SourcePosition>,
+    entered: usize,
+    records: u64,
+    offset: u64,
+}
+
+impl Tracking {
+    fn count(&mut self, consumed: &[u8]) {
+        let records = memchr::memchr_iter(b'\n', consumed).count();
+
+        self.records += u64::try_from(records).unwrap_or(u64::MAX);
+        self.offset += u64::try_from(consumed.len()).unwrap_or(u64::MAX);
+    }
+
+    /// Enters the source at `index`, if it isn't entered yet.
+    fn enter(&mut self, index: usize) {
+        if self.entered != index {
+            self.entered = index;
+            self.position.enter(index, self.records, self.offset);
+        }
+    }
+}
+
+/// Reads multiple sources in sequence as one stream.
+///
+/// A missing newline at the end of a source is added, so that its last line isn't joined with the
+/// first line of the next source. The added newline belongs to the source, which it ends.
+pub(crate) struct SourceChain<R> {
+    sources: std::vec::IntoIter<R>,
+    current: Option<R>,
+    index: usize,
+    finished: Vec<R>,
+    ends_with_newline: bool,
+    pending_newline: bool,
+    tracking: Option<Tracking>,
+}
+
+impl<R: BufRead> SourceChain<R> {
+    pub(crate) fn new(sources: Vec<R>) -> Self {
+        let mut sources = sources.into_iter();
+
+        Self {
+            current: sources.next(),
+            sources,
+            index: 0,
+            finished: Vec::new(),
+            ends_with_newline: true,
+            pending_newline: false,
+            tracking: None,
+        }
+    }
+
+    /// Tracks the source of the read records and its start in `position`.
+    pub(crate) fn track(mut self, position: Arc<SourcePosition>) -> Self {
+        position.enter(self.index, 0, 0);
+        self.tracking = Some(Tracking {
+            position,
+            entered: self.index,
+            records: 0,
+            offset: 0,
+        });
+        self
+    }
+
+    /// Consumes the [`SourceChain`], returning all sources in order.
+    pub(crate) fn into_sources(self) -> Vec<R> {
+        self.finished
+            .into_iter()
+            .chain(self.current)
+            .chain(self.sources)
+            .collect()
+    }
+
+    fn is_current_exhausted(&mut self) -> io::Result<bool> {
+        if self.pending_newline {
+            return Ok(false);
+        }
+        match &mut self.current {
+            Some(current) => Ok(current.fill_buf()?.is_empty()),
+            None => Ok(false),
+        }
+    }
+
+    fn next_source(&mut self) {
+        self.pending_newline = !self.ends_with_newline;
+        self.ends_with_newline = true;
+        self.finished
+            .extend(std::mem::replace(&mut self.current, self.sources.next()));
+        self.index += 1;
+    }
+
+    fn consume_current(&mut self, amt: usize) {
+        let Some(current) = &mut self.current else {
+            return;
+        };
+        if let Some(consumed) = current.fill_buf().ok().and_then(|buffer| buffer.get(..amt)) {
+            self.ends_with_newline = consumed.last() == Some(&b'\n');
+            if let Some(tracking) = &mut self.tracking {
+                tracking.count(consumed);
+            }
+        }
+        current.consume(amt);
+    }
+}
+
+impl<R: BufRead> Read for SourceChain<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+
+        Ok(len)
+    }
+}
+
+impl<R: BufRead> BufRead for SourceChain<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.is_current_exhausted()? {
+            self.next_source();
+        }
+
+        if self.pending_newline {
+            return Ok(b"\n");
+        }
+        // The previous source is entirely consumed, so its records were already split.
+        if let Some(tracking) = &mut self.tracking {
+            tracking.enter(self.index);
+        }
+        match &mut self.current {
+            Some(current) => current.fill_buf(),
+            None => Ok(&[]),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if amt == 0 {
+            return;
+        }
+        if self.pending_newline {
+            self.pending_newline = false;
+            if let Some(tracking) = &mut self.tracking {
+                tracking.count(b"\n");
+            }
+            return;
+        }
+        self.consume_current(amt);
+    }
+}
@@ -13,3 +13,110 @@ fn path_as_path_buf() {
         Source::PathBuf(PathBuf::from(path))
     );
 }
+
+#[test]
+fn pattern_as_glob() {
+    assert_eq!(
+        Source::from_os_str("app.log.*".as_ref()),
+        Source::Glob("app.log.*".to_owned())
+    );
+}
+
+#[test]
+fn existing_pattern_as_path_buf() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let path = dir.path().join("[literal].txt");
+    std::fs::write(&path, "0\n").unwrap();
+
+    assert_eq!(Source::from_os_str(path.as_os_str()), Source::PathBuf(path));
+}
+
+#[test]
+fn expand_path_buf() {
+    let source = Source::PathBuf(PathBuf::from("test.txt"));
+
+    assert_eq!(source.expand().unwrap(), vec![source]);
+}
+
+#[test]
+fn natural_order_of_numbers() {
+    let mut paths = [
+        "app.log.10",
+        "app.log",
+        "app.log.2",
+        "app.log.02",
+        "app.log.1a",
+    ]
+    .map(PathBuf::from)
+    .to_vec();
+
+    paths.sort_by(|left, right| natural_order(left, right));
+
+    assert_eq!(
+        paths,
+        [
+            "app.log",
+            "app.log.1a",
+            "app.log.2",
+            "app.log.02",
+            "app.log.10"
+        ]
+        .map(PathBuf::from)
+    );
+}
+
+#[test]
+fn expand_glob_without_match() {
+    let source = Source::Glob("no_match_for_zsplit_*.txt".to_owned());
+
+    assert_eq!(
+        *source.expand().unwrap_err().current_context(),
+        crate::Error::Source
+    );
+}
+
+fn chain(sources: &[&'static str]) -> String {
+    let sources = sources
+        .iter()
+        .map(|source| Box::new(source.as_bytes()) as Box<dyn BufRead>)
+        .collect();
+
+    let mut output = String::new();
+    SourceChain::new(sources)
+        .read_to_string(&mut output)
+        .unwrap();
+    output
+}
+
+#[test]
+fn chain_sources() {
+    assert_eq!(chain(&["0\n1\n", "2\n"]), "0\n1\n2\n");
+}
+
+#[test]
+fn chain_adds_missing_newline() {
+    assert_eq!(chain(&["0\n1", "2", "3\n"]), "0\n1\n2\n3\n");
+}
+
+#[test]
+fn track_chained_sources() {
+    let sources = ["0\n1", "", "2\n"].map(str::as_bytes).to_vec();
+    let position = Arc::new(SourcePosition::default());
+    let mut chain = SourceChain::new(sources).track(Arc::clone(&position));
+    let mut record = String::new();
+    let mut next_position = || {
+        chain.read_line(&mut record).unwrap();
+        (position.index(), position.record(), position.offset())
+    };
+
+    assert_eq!(next_position(), (0, 0, 0));
+    assert_eq!(next_position(), (0, 0, 0));
+    assert_eq!(next_position(), (2, 2, 4));
+    assert_eq!(record, "0\n1\n2\n");
+}
+
+#[test]
+fn chain_empty_sources() {
+    assert_eq!(chain(&[]), "");
+    assert_eq!(chain(&["", "0\n", "", "1"]), "0\n1\n");
+}
@@ -1,8 +1,9 @@
 use assert_cmd::Command;
 use assert_fs::prelude::*;
-use assert_fs::NamedTempFile;
+use assert_fs::{NamedTempFile, TempDir};
 use itertools::Itertools;
 use std::fs::read_to_string;
+use std::io::Write;
 use std::path::Path;
 
 fn seq(from: isize, to: isize, step_width: usize) -> String {
@@ -43,6 +44,18 @@ fn zsplit() -> Command {
     Command::from_std(runner.command())
 }
 
+#[cfg(any(feature = "gzip", feature = "mmap"))]
+fn zsplit_with_features(features: &str) -> Command {
+    let runner = escargot::CargoBuild::new()
+        .bin("zsplit")
+        .release()
+        .features(features)
+        .run()
+        .unwrap();
+
+    Command::from_std(runner.command())
+}
+
 #[test]
 fn simple() {
     let input = NamedTempFile::new("simple_input").unwrap();
@@ -235,3 +248,1055 @@ fn usage_error() {
     input.close().unwrap();
     close(outputs);
 }
+
+#[test]
+fn multiple_sources() {
+    let inputs = [
+        NamedTempFile::new("multiple_sources_input_0").unwrap(),
+        NamedTempFile::new("multiple_sources_input_1").unwrap(),
+    ];
+    inputs[0].write_str(&seq(0, 4, 1)).unwrap();
+    inputs[1].write_str("5\n6").unwrap();
+
+    let outputs = outputs("multiple_sources", 2);
+
+    zsplit()
+        .arg(inputs[0].path())
+        .args(&paths(&outputs))
+        .arg("--source")
+        .arg(inputs[1].path())
+        .assert()
+        .success();
+
+    assert_eq!(read_to_string(&outputs[0]).unwrap(), seq(0, 6, 2));
+    assert_eq!(read_to_string(&outputs[1]).unwrap(), seq(1, 6, 2));
+
+    close(inputs.into());
+    close(outputs);
+}
+
+#[test]
+fn multiple_sources_restarted() {
+    let inputs = [
+        NamedTempFile::new("multiple_sources_restarted_input_0").unwrap(),
+        NamedTempFile::new("multiple_sources_restarted_input_1").unwrap(),
+    ];
+    inputs[0].write_str("0\n1\n2\n").unwrap();
+    inputs[1].write_str("3\n4\n").unwrap();
+
+    let outputs = outputs("multiple_sources_restarted", 2);
+
+    zsplit()
+        .arg(inputs[0].path())
+        .args(&paths(&outputs))
+        .arg("--source")
+        .arg(inputs[1].path())
+        .arg("--restart-per-source")
+        .assert()
+        .success();
+
+    assert_eq!(read_to_string(&outputs[0]).unwrap(), "0\n2\n3\n");
+    assert_eq!(read_to_string(&outputs[1]).unwrap(), "1\n4\n");
+
+    close(inputs.into());
+    close(outputs);
+}
+
+#[test]
+fn glob_source() {
+    let input = TempDir::new().unwrap();
+    input.child("app.log.2").write_str(&seq(0, 4, 1)).unwrap();
+    input.child("app.log.10").write_str(&seq(5, 9, 1)).unwrap();
+    input.child("other.log").write_str("42\n").unwrap();
+
+    let outputs = outputs("glob_source", 2);
+
+    zsplit()
+        .arg(input.path().join("app.log.*"))
+        .args(&paths(&outputs))
+        .assert()
+        .success();
+
+    assert_eq!(read_to_string(&outputs[0]).unwrap(), seq(0, 9, 2));
+    assert_eq!(read_to_string(&outputs[1]).unwrap(), seq(1, 9, 2));
+
+    input.close().unwrap();
+    close(outputs);
+}
+
+#[test]
+fn glob_source_without_match() {
+    let input = TempDir::new().unwrap();
+    let outputs = outputs("glob_source_without_match", 2);
+
+    zsplit()
+        .arg(input.path().join("app.log.*"))
+        .args(&paths(&outputs))
+        .assert()
+        .failure()
+        .code(i32::from(sysexits::ExitCode::NoInput));
+
+    input.close().unwrap();
+    close(outputs);
+}
+
+#[test]
+fn generated_destinations() {
+    let output = TempDir::new().unwrap();
+
+    zsplit()
+        .write_stdin(seq(0, 1000, 1))
+        .arg("-")
+        .args(["--count", "3", "--numeric-suffixes", "--prefix"])
+        .arg(output.path().join("part_"))
+        .assert()
+        .success();
+
+    for i in 0..3 {
+        assert_eq!(
+            read_to_string(output.path().join(format!("part_0{i}"))).unwrap(),
+            seq(i, 1000, 3)
+        );
+    }
+
+    output.close().unwrap();
+}
+
+#[test]
+fn template_destinations() {
+    let output = TempDir::new().unwrap();
+
+    zsplit()
+        .write_stdin(seq(0, 1000, 1))
+        .arg("-")
+        .args(["--count", "2", "--template"])
+        .arg(output.path().join("out_{index:03}.txt"))
+        .assert()
+        .success();
+
+    for i in 0..2 {
+        assert_eq!(
+            read_to_string(output.path().join(format!("out_00{i}.txt"))).unwrap(),
+            seq(i, 1000, 2)
+        );
+    }
+
+    output.close().unwrap();
+}
+
+#[test]
+fn output_dir_with_manifest() {
+    let output = TempDir::new().unwrap();
+    let output_dir = output.path().join("nested").join("shards");
+
+    zsplit()
+        .write_stdin(seq(0, 1000, 1))
+        .arg("-")
+        .args(["--count", "2", "--manifest", "--output-dir"])
+        .arg(&output_dir)
+        .assert()
+        .success();
+
+    assert_eq!(
+        read_to_string(output_dir.join("manifest.txt")).unwrap(),
+        "xaa\nxab\n"
+    );
+    assert_eq!(
+        read_to_string(output_dir.join("xaa")).unwrap(),
+        seq(0, 1000, 2)
+    );
+    assert_eq!(
+        read_to_string(output_dir.join("xab")).unwrap(),
+        seq(1, 1000, 2)
+    );
+
+    output.close().unwrap();
+}
+
+#[test]
+fn json_manifest() {
+    let output = TempDir::new().unwrap();
+    let manifest_path = output.path().join("manifest.json");
+
+    zsplit()
+        .write_stdin("0\n1\n2\n")
+        .arg("-")
+        .args(["--count", "2", "--distributions", "2", "--output-dir"])
+        .arg(output.path())
+        .arg("--json-manifest")
+        .arg(&manifest_path)
+        .assert()
+        .success();
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&read_to_string(manifest_path).unwrap()).unwrap();
+
+    assert_eq!(manifest["sources"][0]["path"], "-");
+    assert_eq!(manifest["sources"][0]["size"], 6);
+    assert_eq!(manifest["strategy"], "round_robin");
+    assert_eq!(manifest["distributions"], serde_json::json!([2, 1]));
+    assert_eq!(manifest["destinations"][0]["lines"], 2);
+    assert_eq!(manifest["destinations"][1]["lines"], 1);
+    assert_eq!(
+        manifest["destinations"][1]["sha256"],
+        "53c234e5e8472b6ac51c1ae1cab3fe06fad053beb8ebfd8977b010655bfdd3c3"
+    );
+
+    output.close().unwrap();
+}
+
+#[test]
+fn stats() {
+    let output = TempDir::new().unwrap();
+
+    let assert = zsplit()
+        .write_stdin(seq(0, 9, 1))
+        .arg("-")
+        .args(["--count", "2", "--stats", "--output-dir"])
+        .arg(output.path())
+        .assert()
+        .success();
+
+    let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+    assert!(stderr.contains("Records written: 10\n"));
+    assert!(stderr.contains("xab: 5 records, 10 bytes\n"));
+
+    output.close().unwrap();
+}
+
+#[test]
+fn atomic_destinations() {
+    let output = TempDir::new().unwrap();
+
+    zsplit()
+        .write_stdin(seq(0, 9, 1))
+        .arg("-")
+        .args(["--count", "2", "--atomic", "--output-dir"])
+        .arg(output.path())
+        .assert()
+        .success();
+
+    let mut files = std::fs::read_dir(output.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect_vec();
+    files.sort();
+    assert_eq!(files, ["xaa", "xab"]);
+    assert_eq!(
+        read_to_string(output.path().join("xab")).unwrap(),
+        seq(1, 9, 2)
+    );
+
+    output.close().unwrap();
+}
+
+#[test]
+fn atomic_destinations_of_failed_split() {
+    let output = TempDir::new().unwrap();
+    output.child("xaa").write_str("previous\n").unwrap();
+
+    zsplit()
+        .write_stdin(b"0\n1\n\xff\n".as_slice())
+        .arg("-")
+        .args(["--count", "2", "--atomic", "--output-dir"])
+        .arg(output.path())
+        .assert()
+        .failure();
+
+    let files = std::fs::read_dir(output.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect_vec();
+    assert_eq!(files, ["xaa"]);
+    assert_eq!(
+        read_to_string(output.path().join("xaa")).unwrap(),
+        "previous\n"
+    );
+
+    output.close().unwrap();
+}
+
+#[test]
+fn append_destinations() {
+    let output = outputs("append_destinations", 2);
+    output[0].write_str("previous\n").unwrap();
+
+    zsplit()
+        .write_stdin("0\n1\n")
+        .arg("-")
+        .args(paths(&output))
+        .arg("--append")
+        .assert()
+        .success();
+
+    assert_eq!(read_to_string(output[0].path()).unwrap(), "previous\n0\n");
+    assert_eq!(read_to_string(output[1].path()).unwrap(), "1\n");
+
+    close(output);
+}
+
+#[test]
+fn no_clobber_destinations() {
+    let output = outputs("no_clobber_destinations", 2);
+    output[1].write_str("previous\n").unwrap();
+
+    zsplit()
+        .write_stdin("0\n1\n")
+        .arg("-")
+        .args(paths(&output))
+        .args(["--no-clobber", "--atomic"])
+        .assert()
+        .failure();
+
+    assert!(!output[0].path().exists());
+    assert_eq!(read_to_string(output[1].path()).unwrap(), "previous\n");
+
+    close(output);
+}
+
+#[test]
+fn rotated_destinations() {
+    let output = TempDir::new().unwrap();
+
+    zsplit()
+        .write_stdin(seq(0, 9, 1))
+        .arg("-")
+        .args(["--count", "2", "--rotate-lines", "2", "--output-dir"])
+        .arg(output.path())
+        .assert()
+        .success();
+
+    assert_eq!(read_to_string(output.path().join("xaa")).unwrap(), "0\n2\n");
+    assert_eq!(
+        read_to_string(output.path().join("xaa.1")).unwrap(),
+        "4\n6\n"
+    );
+    assert_eq!(read_to_string(output.path().join("xaa.2")).unwrap(), "8\n");
+    assert_eq!(read_to_string(output.path().join("xab.2")).unwrap(), "9\n");
+    assert!(!output.path().join("xab.3").exists());
+
+    output.close().unwrap();
+}
+
+#[test]
+fn lazily_opened_destinations() {
+    let output = TempDir::new().unwrap();
+
+    zsplit()
+        .write_stdin(seq(0, 99, 1))
+        .arg("-")
+        .args(["--count", "10", "--max-open", "3", "--numeric-suffixes"])
+        .arg("--output-dir")
+        .arg(output.path())
+        .assert()
+        .success();
+
+    (0..10).for_each(|index| {
+        assert_eq!(
+            read_to_string(output.path().join(format!("x0{index}"))).unwrap(),
+            seq_ring(0, 99, 10, &[index])
+        );
+    });
+
+    output.close().unwrap();
+}
+
+#[test]
+fn key_based_destinations() {
+    let output = TempDir::new().unwrap();
+
+    zsplit()
+        .write_stdin("alice 1\nbob 2\nalice 3\n")
+        .arg("-")
+        .arg("--output-template")
+        .arg(output.path().join("{key}.log"))
+        .assert()
+        .success();
+
+    assert_eq!(
+        read_to_string(output.path().join("alice.log")).unwrap(),
+        "alice 1\nalice 3\n"
+    );
+    assert_eq!(
+        read_to_string(output.path().join("bob.log")).unwrap(),
+        "bob 2\n"
+    );
+
+    output.close().unwrap();
+}
+
+#[test]
+fn key_based_destinations_exceeding_max_keys() {
+    let output = TempDir::new().unwrap();
+
+    zsplit()
+        .write_stdin("a,0\nb,1\n")
+        .arg("-")
+        .args([
+            "--key-delimiter",
+            ",",
+            "--max-keys",
+            "1",
+            "--output-template",
+        ])
+        .arg(output.path().join("{key}"))
+        .assert()
+        .failure();
+
+    output.close().unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn skip_failing_destination() {
+    let output = outputs("skip_failing_destination", 1);
+
+    let assert = zsplit()
+        .write_stdin(seq(0, 9999, 1))
+        .arg("-")
+        .arg("/dev/full")
+        .args(paths(&output))
+        .args(["--on-error", "skip"])
+        .assert()
+        .failure();
+
+    let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+    assert!(stderr.contains("Dropped `/dev/full`"));
+    assert!(read_to_string(output[0].path())
+        .unwrap()
+        .ends_with("9998\n9999\n"));
+
+    close(output);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn failing_destination_location() {
+    let assert = zsplit()
+        .write_stdin(seq(0, 9999, 1))
+        .args(["-", "/dev/full", "/dev/null"])
+        .assert()
+        .failure();
+
+    let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+    assert!(stderr.contains("Destination `/dev/full`"));
+    assert!(stderr.contains("Couldn't write record"));
+}
+
+#[test]
+fn resume_from_checkpoint() {
+    let dir = TempDir::new().unwrap();
+    let [source, a, b, state] = ["source.txt", "a", "b", "state.json"].map(|name| dir.join(name));
+    std::fs::write(&source, seq(0, 9, 1)).unwrap();
+    std::fs::write(&a, "0\n2\nXX").unwrap();
+    std::fs::write(&b, "1\n3\n").unwrap();
+    std::fs::write(
+        &state,
+        serde_json::json!({
+            "source": source,
+            "line_factor": 1,
+            "distributions": [1, 1],
+            "record": 4,
+            "offset": 8,
+            "destinations": [{ "path": a, "bytes": 4 }, { "path": b, "bytes": 4 }],
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    zsplit()
+        .args([&source, &a, &b])
+        .arg("--checkpoint")
+        .arg(&state)
+        .arg("--resume")
+        .assert()
+        .success();
+
+    assert_eq!(read_to_string(&a).unwrap(), seq(0, 9, 2));
+    assert_eq!(read_to_string(&b).unwrap(), seq(1, 9, 2));
+    assert!(!state.exists());
+
+    dir.close().unwrap();
+}
+
+#[test]
+fn resume_annotated_checkpoint() {
+    let dir = TempDir::new().unwrap();
+    let [source, a, b, state] = ["source.txt", "a", "b", "state.json"].map(|name| dir.join(name));
+    std::fs::write(&source, seq(0, 9, 1)).unwrap();
+    std::fs::write(&a, "0\t0\n2\t2\nXX").unwrap();
+    std::fs::write(&b, "1\t1\n3\t3\n").unwrap();
+    std::fs::write(
+        &state,
+        serde_json::json!({
+            "source": source,
+            "line_factor": 1,
+            "distributions": [1, 1],
+            "record": 4,
+            "offset": 8,
+            "destinations": [{ "path": a, "bytes": 8 }, { "path": b, "bytes": 8 }],
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    zsplit()
+        .args([&source, &a, &b])
+        .arg("--checkpoint")
+        .arg(&state)
+        .arg("--resume")
+        .args(["--annotate", "number"])
+        .assert()
+        .success();
+
+    let annotated = |from| {
+        (from..10)
+            .step_by(2)
+            .map(|record| format!("{record}\t{record}\n"))
+    };
+    assert_eq!(
+        read_to_string(&a).unwrap(),
+        annotated(0).collect::<String>()
+    );
+    assert_eq!(
+        read_to_string(&b).unwrap(),
+        annotated(1).collect::<String>()
+    );
+
+    dir.close().unwrap();
+}
+
+#[test]
+fn resume_other_distributions() {
+    let dir = TempDir::new().unwrap();
+    let [source, a, b, state] = ["source.txt", "a", "b", "state.json"].map(|name| dir.join(name));
+    std::fs::write(&source, seq(0, 9, 1)).unwrap();
+    std::fs::write(&a, "0\n2\n").unwrap();
+    std::fs::write(&b, "1\n3\n").unwrap();
+    std::fs::write(
+        &state,
+        serde_json::json!({
+            "source": source,
+            "line_factor": 1,
+            "distributions": [1, 1],
+            "record": 4,
+            "offset": 8,
+            "destinations": [{ "path": a, "bytes": 4 }, { "path": b, "bytes": 4 }],
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    zsplit()
+        .args([&source, &a, &b])
+        .args(["--distributions", "2"])
+        .arg("--checkpoint")
+        .arg(&state)
+        .arg("--resume")
+        .assert()
+        .failure()
+        .code(i32::from(sysexits::ExitCode::DataErr));
+
+    assert_eq!(read_to_string(&a).unwrap(), "0\n2\n");
+    assert!(state.exists());
+
+    dir.close().unwrap();
+}
+
+#[test]
+fn checkpoint_of_stdin() {
+    let output = outputs("checkpoint_of_stdin", 2);
+    let state = TempDir::new().unwrap();
+
+    zsplit()
+        .write_stdin(seq(0, 9, 1))
+        .arg("-")
+        .args(paths(&output))
+        .arg("--checkpoint")
+        .arg(state.join("state.json"))
+        .assert()
+        .failure();
+
+    close(output);
+    state.close().unwrap();
+}
+
+/// Waits until the file at `path` contains `expected`.
+fn wait_for_content(path: &Path, expected: &str) {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+
+    while read_to_string(path).unwrap_or_default() != expected {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "`{}` doesn't contain {expected:?}",
+            path.display()
+        );
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn follow_growing_source() {
+    let dir = TempDir::new().unwrap();
+    let [source, a, b] = ["source.log", "a", "b"].map(|name| dir.join(name));
+    std::fs::write(&source, seq(0, 3, 1)).unwrap();
+
+    let mut child = escargot::CargoBuild::new()
+        .bin("zsplit")
+        .release()
+        .run()
+        .unwrap()
+        .command()
+        .args([&source, &a, &b])
+        .arg("--follow")
+        .spawn()
+        .unwrap();
+
+    wait_for_content(&a, &seq(0, 3, 2));
+    wait_for_content(&b, &seq(1, 3, 2));
+
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(&source)
+        .unwrap()
+        .write_all(seq(4, 7, 1).as_bytes())
+        .unwrap();
+
+    wait_for_content(&a, &seq(0, 7, 2));
+    wait_for_content(&b, &seq(1, 7, 2));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+    dir.close().unwrap();
+}
+
+#[test]
+fn follow_multiple_sources() {
+    let output = outputs("follow_multiple_sources", 2);
+
+    zsplit()
+        .args(["a.log", "--source", "b.log", "--follow"])
+        .args(&paths(&output))
+        .assert()
+        .failure()
+        .code(2);
+
+    close(output);
+}
+
+#[test]
+fn flush_every_record() {
+    let dir = TempDir::new().unwrap();
+    let [a, b] = ["a", "b"].map(|name| dir.join(name));
+
+    let mut child = escargot::CargoBuild::new()
+        .bin("zsplit")
+        .release()
+        .run()
+        .unwrap()
+        .command()
+        .args(["-".as_ref(), a.as_os_str(), b.as_os_str()])
+        .args(["--flush", "record"])
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+
+    stdin.write_all(seq(0, 3, 1).as_bytes()).unwrap();
+    stdin.flush().unwrap();
+
+    // The records arrive, while STDIN is still open.
+    wait_for_content(&a, &seq(0, 3, 2));
+    wait_for_content(&b, &seq(1, 3, 2));
+
+    drop(stdin);
+    assert!(child.wait().unwrap().success());
+    dir.close().unwrap();
+}
+
+#[test]
+fn invalid_flush_policy() {
+    let output = outputs("invalid_flush_policy", 2);
+
+    zsplit()
+        .write_stdin(seq(0, 9, 1))
+        .arg("-")
+        .args(&paths(&output))
+        .args(["--flush", "1s"])
+        .assert()
+        .failure()
+        .code(2);
+
+    close(output);
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn mapped_source() {
+    let input = NamedTempFile::new("mapped_source_input").unwrap();
+    input.write_str("0\r\n1\n2\n3").unwrap();
+    let output = outputs("mapped_source", 2);
+
+    zsplit_with_features("mmap")
+        .arg(input.path())
+        .args(&paths(&output))
+        .arg("--distributions")
+        .args(["3", "1"])
+        .assert()
+        .success();
+
+    assert_eq!(read_to_string(&output[0]).unwrap(), "0\n1\n2\n");
+    assert_eq!(read_to_string(&output[1]).unwrap(), "3\n");
+
+    close(output);
+}
+
+#[test]
+fn contiguous_chunks() {
+    let input = NamedTempFile::new("contiguous_chunks_input").unwrap();
+    input.write_str(&seq(0, 9, 1)).unwrap();
+    let output = outputs("contiguous_chunks", 3);
+
+    zsplit()
+        .arg(input.path())
+        .args(&paths(&output))
+        .args(["--contiguous", "--distributions", "2", "1", "2"])
+        .assert()
+        .success();
+
+    assert_eq!(read_to_string(&output[0]).unwrap(), seq(0, 3, 1));
+    assert_eq!(read_to_string(&output[1]).unwrap(), seq(4, 5, 1));
+    assert_eq!(read_to_string(&output[2]).unwrap(), seq(6, 9, 1));
+
+    input.close().unwrap();
+    close(output);
+}
+
+#[test]
+fn contiguous_from_stdin() {
+    let output = outputs("contiguous_from_stdin", 2);
+
+    zsplit()
+        .write_stdin(seq(0, 9, 1))
+        .arg("-")
+        .args(&paths(&output))
+        .arg("--contiguous")
+        .assert()
+        .failure()
+        .code(i32::from(sysexits::ExitCode::Usage));
+
+    close(output);
+}
+
+#[test]
+fn truncate_oversized_records() {
+    let output = outputs("truncate_oversized_records", 2);
+
+    zsplit()
+        .write_stdin("0\n123456\n2\n3\n")
+        .arg("-")
+        .args(&paths(&output))
+        .args(["--max-record-length", "3", "--oversized", "truncate"])
+        .assert()
+        .success();
+
+    assert_eq!(read_to_string(&output[0]).unwrap(), "0\n2\n");
+    assert_eq!(read_to_string(&output[1]).unwrap(), "123\n3\n");
+
+    close(output);
+}
+
+#[test]
+fn fail_on_oversized_record() {
+    let output = outputs("fail_on_oversized_record", 2);
+
+    zsplit()
+        .write_stdin("0\n123456\n2\n")
+        .arg("-")
+        .args(&paths(&output))
+        .args(["--max-record-length", "3"])
+        .assert()
+        .failure();
+
+    close(output);
+}
+
+#[test]
+fn lines_range() {
+    let output = outputs("lines_range", 2);
+
+    zsplit()
+        .write_stdin(seq(0, 99, 1))
+        .arg("-")
+        .args(&paths(&output))
+        .args(["--lines", "10..20"])
+        .assert()
+        .success();
+
+    assert_eq!(read_to_string(&output[0]).unwrap(), seq(10, 19, 2));
+    assert_eq!(read_to_string(&output[1]).unwrap(), seq(11, 19, 2));
+
+    close(output);
+}
+
+#[test]
+fn skip_and_head() {
+    let output = outputs("skip_and_head", 2);
+
+    zsplit()
+        .write_stdin(seq(0, 99, 1))
+        .arg("-")
+        .args(&paths(&output))
+        .args(["--skip", "95", "--head", "3"])
+        .assert()
+        .success();
+
+    assert_eq!(read_to_string(&output[0]).unwrap(), "95\n97\n");
+    assert_eq!(read_to_string(&output[1]).unwrap(), "96\n");
+
+    close(output);
+}
+
+#[test]
+fn annotate_numbers_and_offsets() {
+    let output = outputs("annotate_numbers_and_offsets", 2);
+
+    zsplit()
+        .write_stdin("a\nbb\nc\n")
+        .arg("-")
+        .args(&paths(&output))
+        .args(["--annotate", "number,offset", "--annotation-separator", ":"])
+        .assert()
+        .success();
+
+    assert_eq!(read_to_string(&output[0]).unwrap(), "0:0:a\n2:5:c\n");
+    assert_eq!(read_to_string(&output[1]).unwrap(), "1:2:bb\n");
+
+    close(output);
+}
+
+#[test]
+fn annotate_sources() {
+    let inputs = [
+        NamedTempFile::new("annotate_sources_input_0").unwrap(),
+        NamedTempFile::new("annotate_sources_input_1").unwrap(),
+    ];
+    inputs[0].write_str("0\n1\n").unwrap();
+    inputs[1].write_str("2\n").unwrap();
+
+    let output = outputs("annotate_sources", 2);
+
+    zsplit()
+        .arg(inputs[0].path())
+        .args(&paths(&output))
+        .arg("--source")
+        .arg(inputs[1].path())
+        .args(["--annotate", "source,number", "--restart-per-source"])
+        .assert()
+        .success();
+
+    assert_eq!(
+        read_to_string(&output[0]).unwrap(),
+        format!(
+            "{0}\t0\t0\n{1}\t0\t2\n",
+            inputs[0].path().display(),
+            inputs[1].path().display()
+        )
+    );
+    assert_eq!(
+        read_to_string(&output[1]).unwrap(),
+        format!("{}\t1\t1\n", inputs[0].path().display())
+    );
+
+    close(inputs.into());
+    close(output);
+}
+
+#[test]
+fn annotate_chained_sources() {
+    let inputs = [
+        NamedTempFile::new("annotate_chained_sources_input_0").unwrap(),
+        NamedTempFile::new("annotate_chained_sources_input_1").unwrap(),
+    ];
+    inputs[0].write_str("0\n1").unwrap();
+    inputs[1].write_str("2\n").unwrap();
+
+    let output = outputs("annotate_chained_sources", 2);
+
+    zsplit()
+        .arg(inputs[0].path())
+        .args(&paths(&output))
+        .arg("--source")
+        .arg(inputs[1].path())
+        .args(["--annotate", "source,number,offset"])
+        .assert()
+        .success();
+
+    assert_eq!(
+        read_to_string(&output[0]).unwrap(),
+        format!(
+            "{0}\t0\t0\t0\n{1}\t0\t0\t2\n",
+            inputs[0].path().display(),
+            inputs[1].path().display()
+        )
+    );
+    assert_eq!(
+        read_to_string(&output[1]).unwrap(),
+        format!("{}\t1\t2\t1\n", inputs[0].path().display())
+    );
+
+    close(inputs.into());
+    close(output);
+}
+
+#[test]
+fn dedup_records() {
+    let inputs = [
+        NamedTempFile::new("dedup_records_input_0").unwrap(),
+        NamedTempFile::new("dedup_records_input_1").unwrap(),
+    ];
+    inputs[0].write_str("a\nb\na\n").unwrap();
+    inputs[1].write_str("c\nb\nd\n").unwrap();
+
+    let output = outputs("dedup_records", 2);
+    let duplicates = NamedTempFile::new("dedup_records_duplicates").unwrap();
+
+    zsplit()
+        .arg(inputs[0].path())
+        .args(&paths(&output))
+        .arg("--source")
+        .arg(inputs[1].path())
+        .arg("--dedup")
+        .arg("--duplicates")
+        .arg(duplicates.path())
+        .assert()
+        .success();
+
+    assert_eq!(read_to_string(&output[0]).unwrap(), "a\nc\n");
+    assert_eq!(read_to_string(&output[1]).unwrap(), "b\nd\n");
+    assert_eq!(read_to_string(&duplicates).unwrap(), "a\nb\n");
+
+    close(inputs.into());
+    close(output);
+    duplicates.close().unwrap();
+}
+
+#[test]
+fn dedup_with_bounded_memory() {
+    let output = outputs("dedup_with_bounded_memory", 2);
+
+    zsplit()
+        .write_stdin(seq(0, 99, 1) + &seq(0, 99, 1))
+        .arg("-")
+        .args(&paths(&output))
+        .args(["--dedup", "--dedup-memory", "4096"])
+        .assert()
+        .success();
+
+    assert_eq!(read_to_string(&output[0]).unwrap(), seq(0, 99, 2));
+    assert_eq!(read_to_string(&output[1]).unwrap(), seq(1, 99, 2));
+
+    close(output);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn gzip_by_extension() {
+    let outputs = vec![
+        NamedTempFile::new("gzip_by_extension_output_0.gz").unwrap(),
+        NamedTempFile::new("gzip_by_extension_output_1").unwrap(),
+    ];
+
+    zsplit_with_features("gzip")
+        .write_stdin(seq(0, 1000, 1))
+        .arg("-")
+        .args(&paths(&outputs))
+        .assert()
+        .success();
+
+    assert!(std::fs::read(&outputs[0]).unwrap().starts_with(GZIP_MAGIC));
+    assert_eq!(read_to_string(&outputs[1]).unwrap(), seq(1, 1000, 2));
+
+    close(outputs);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn gzip_forced() {
+    let outputs = outputs("gzip_forced", 2);
+
+    zsplit_with_features("gzip")
+        .write_stdin(seq(0, 1000, 1))
+        .arg("-")
+        .args(&paths(&outputs))
+        .args(["--compress", "gzip"])
+        .assert()
+        .success();
+
+    for output in &outputs {
+        assert!(std::fs::read(output).unwrap().starts_with(GZIP_MAGIC));
+    }
+
+    close(outputs);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn gzip_source() {
+    let input = gzip_input("gzip_source_input", &seq(0, 1000, 1));
+    let outputs = outputs("gzip_source", 2);
+
+    zsplit_with_features("gzip")
+        .arg(input.path())
+        .args(&paths(&outputs))
+        .assert()
+        .success();
+
+    assert_eq!(read_to_string(&outputs[0]).unwrap(), seq(0, 1000, 2));
+    assert_eq!(read_to_string(&outputs[1]).unwrap(), seq(1, 1000, 2));
+
+    input.close().unwrap();
+    close(outputs);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn gzip_source_pipe() {
+    let input = gzip_input("gzip_source_pipe_input", &seq(0, 1000, 1));
+    let outputs = outputs("gzip_source_pipe", 2);
+
+    zsplit_with_features("gzip")
+        .write_stdin(std::fs::read(input.path()).unwrap())
+        .arg("-")
+        .args(&paths(&outputs))
+        .assert()
+        .success();
+
+    assert_eq!(read_to_string(&outputs[0]).unwrap(), seq(0, 1000, 2));
+    assert_eq!(read_to_string(&outputs[1]).unwrap(), seq(1, 1000, 2));
+
+    input.close().unwrap();
+    close(outputs);
+}
+
+#[cfg(feature = "gzip")]
+fn gzip_input(name: &str, content: &str) -> NamedTempFile {
+    use std::io::Write;
+    use zsplit::compression::Compression;
+    use zsplit::destination::Finish;
+    use zsplit::file::FileOptions;
+
+    let input = NamedTempFile::new(name).unwrap();
+
+    let mut sink = FileOptions::new()
+        .compression(Compression::Gzip)
+        .open(input.path())
+        .unwrap();
+    sink.write_all(content.as_bytes()).unwrap();
+    sink.finish().unwrap();
+
+    input
+}
+
+#[cfg(feature = "gzip")]
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
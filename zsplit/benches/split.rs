@@ -0,0 +1,103 @@
+//! Throughput of the splitting algorithms.
+// `criterion_group!` generates an undocumented public function.
+#![allow(missing_docs)]
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::fmt::Write as _;
+use std::io;
+use zsplit::keyed::KeyedDestinations;
+use zsplit::prelude::*;
+
+const RECORDS: usize = 200_000;
+
+/// Log like records with a user as the first field.
+fn source(line_ending: &str) -> String {
+    (0..RECORDS).fold(String::new(), |mut source, record| {
+        write!(
+            source,
+            "user{} GET /api/items/{record} 200{line_ending}",
+            record % 16
+        )
+        .expect("Writing to a String can't fail");
+        source
+    })
+}
+
+fn round_robin(c: &mut Criterion) {
+    let mut group = c.benchmark_group("round_robin");
+
+    for (name, line_ending, assigned_lines) in
+        [("lf", "\n", 1), ("lf", "\n", 1000), ("crlf", "\r\n", 1)]
+    {
+        let source = source(line_ending);
+        group.throughput(Throughput::Bytes(
+            source.len().try_into().unwrap_or(u64::MAX),
+        ));
+
+        group.bench_with_input(
+            BenchmarkId::new(name, assigned_lines),
+            &source,
+            |b, source| {
+                b.iter(|| {
+                    let mut destinations: Vec<_> = (0..4)
+                        .map(|_| Destination::new(io::sink(), assigned_lines))
+                        .collect();
+
+                    split_round_robin(&mut source.as_bytes(), &mut destinations)
+                        .expect("Splitting into sinks can't fail")
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+fn by_key(c: &mut Criterion) {
+    let source = source("\n");
+    let mut group = c.benchmark_group("by_key");
+    group.throughput(Throughput::Bytes(
+        source.len().try_into().unwrap_or(u64::MAX),
+    ));
+
+    group.bench_function("user", |b| {
+        b.iter(|| {
+            split_by_key(
+                &mut source.as_bytes(),
+                &mut KeyedDestinations::new(),
+                |line| line.split(' ').next().unwrap_or_default(),
+                |_key| Ok(io::sink()),
+            )
+            .expect("Splitting into sinks can't fail")
+        });
+    });
+    group.finish();
+}
+
+fn contiguous(c: &mut Criterion) {
+    let source = source("\n");
+    let mut group = c.benchmark_group("contiguous");
+    group.throughput(Throughput::Bytes(
+        source.len().try_into().unwrap_or(u64::MAX),
+    ));
+
+    for destinations_len in [1, 4] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(destinations_len),
+            &source,
+            |b, source| {
+                b.iter(|| {
+                    let mut destinations: Vec<_> = (0..destinations_len)
+                        .map(|_| Destination::new_with_sink(io::sink()))
+                        .collect();
+
+                    split_contiguous(|| Ok(io::Cursor::new(source)), &mut destinations)
+                        .expect("Splitting into sinks can't fail")
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, round_robin, by_key, contiguous);
+criterion_main!(benches);
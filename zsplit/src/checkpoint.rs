@@ -0,0 +1,45 @@
+//! Progress of a splitting operation, from which it can be resumed.
+
+use crate::error::Position;
+
+/// The progress of a round robin split, when all records up to it are written and flushed.
+///
+/// See [`split_round_robin_resumable`](crate::split::round_robin_resumable) for saving and
+/// resuming it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Checkpoint {
+    /// The number of records read from the source.
+    pub record: u64,
+
+    /// The byte offset in the source behind the last record.
+    pub offset: u64,
+
+    /// The bytes written to every destination in the same order as the destinations.
+    pub destinations: Vec<u64>,
+}
+
+impl Checkpoint {
+    /// Creates a [`Checkpoint`] from its saved parts.
+    #[must_use]
+    #[inline]
+    pub fn new(record: u64, offset: u64, destinations: Vec<u64>) -> Self {
+        Self {
+            record,
+            offset,
+            destinations,
+        }
+    }
+
+    pub(crate) fn position(&self) -> Position {
+        Position {
+            record: self.record,
+            offset: self.offset,
+        }
+    }
+
+    /// The bytes written to the destination at `index`.
+    pub(crate) fn bytes(&self, index: usize) -> u64 {
+        self.destinations.get(index).copied().unwrap_or_default()
+    }
+}
@@ -0,0 +1,419 @@
+//! Compression of path based destinations and decompression of sources.
+//!
+//! Every format is behind a cargo feature of the same name:
+//!
+//! | Format | Feature | Extension |
+//! |--------|---------|-----------|
+//! | gzip   | `gzip`  | `.gz`     |
+//! | zstd   | `zstd`  | `.zst`    |
+//! | bzip2  | `bzip2` | `.bz2`    |
+//! | xz     | `xz`    | `.xz`     |
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+#[cfg(test)]
+#[path = "./compression_test.rs"]
+mod compression_test;
+
+/// The length of the start of a file, which contains the magic bytes of every format.
+const MAGIC_LEN: usize = 6;
+
+/// The compression format of a file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Compression {
+    /// Writes the data as is.
+    #[default]
+    None,
+    /// Compresses with gzip.
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// Compresses with Zstandard.
+    #[cfg(feature = "zstd")]
+    Zstd,
+    /// Compresses with bzip2.
+    #[cfg(feature = "bzip2")]
+    Bzip2,
+    /// Compresses with xz.
+    #[cfg(feature = "xz")]
+    Xz,
+}
+
+impl Compression {
+    /// All formats enabled by cargo features, including [`Compression::None`].
+    pub const ENABLED: &'static [Self] = &[
+        Self::None,
+        #[cfg(feature = "gzip")]
+        Self::Gzip,
+        #[cfg(feature = "zstd")]
+        Self::Zstd,
+        #[cfg(feature = "bzip2")]
+        Self::Bzip2,
+        #[cfg(feature = "xz")]
+        Self::Xz,
+    ];
+
+    /// Detects the [`Compression`] by the extension of the `path`.
+    ///
+    /// Only the formats of the enabled features are detected. Everything else is [`Compression::None`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use zsplit::compression::Compression;
+    ///
+    /// assert_eq!(Compression::from_path("a.txt"), Compression::None);
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        path.as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Self::from_extension)
+            .unwrap_or(Self::None)
+    }
+
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            #[cfg(feature = "gzip")]
+            "gz" => Some(Self::Gzip),
+            #[cfg(feature = "zstd")]
+            "zst" => Some(Self::Zstd),
+            #[cfg(feature = "bzip2")]
+            "bz2" => Some(Self::Bzip2),
+            #[cfg(feature = "xz")]
+            "xz" => Some(Self::Xz),
+            _ => None,
+        }
+    }
+
+    /// The file extension without the leading dot.
+    #[must_use]
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            #[cfg(feature = "gzip")]
+            Self::Gzip => Some("gz"),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Some("zst"),
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2 => Some("bz2"),
+            #[cfg(feature = "xz")]
+            Self::Xz => Some("xz"),
+        }
+    }
+
+    /// Detects the [`Compression`] by the magic bytes at the start of the `reader`.
+    ///
+    /// Nothing is consumed from the `reader`, so only its buffer is inspected, which should hold
+    /// the first 6 bytes. [`decompress`] reads them even from a shorter buffer.
+    /// Only the formats of the enabled features are detected. Everything else is
+    /// [`Compression::None`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` when the `reader` can't be read.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use zsplit::compression::Compression;
+    ///
+    /// let mut source = std::io::BufReader::new("Hello\nWorld".as_bytes());
+    ///
+    /// assert_eq!(Compression::from_magic_bytes(&mut source).unwrap(), Compression::None);
+    /// ```
+    pub fn from_magic_bytes<R: BufRead>(reader: &mut R) -> io::Result<Self> {
+        Ok(Self::from_start(reader.fill_buf()?))
+    }
+
+    /// Detects the [`Compression`] by the magic bytes at the `start` of a file.
+    fn from_start(start: &[u8]) -> Self {
+        Self::ENABLED
+            .iter()
+            .copied()
+            .find(|compression| compression.is_magic_start(start))
+            .unwrap_or(Self::None)
+    }
+
+    /// Whether the `start` of a file begins with the magic bytes of the format.
+    fn is_magic_start(self, start: &[u8]) -> bool {
+        // The magic bytes of bzip2 are followed by the block size from 1 to 9.
+        #[cfg(feature = "bzip2")]
+        if self == Self::Bzip2
+            && !start
+                .get(3)
+                .map_or(false, |block_size| (b'1'..=b'9').contains(block_size))
+        {
+            return false;
+        }
+
+        self.magic_bytes()
+            .map_or(false, |magic_bytes| start.starts_with(magic_bytes))
+    }
+
+    fn magic_bytes(self) -> Option<&'static [u8]> {
+        match self {
+            Self::None => None,
+            #[cfg(feature = "gzip")]
+            Self::Gzip => Some(&[0x1f, 0x8b]),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Some(&[0x28, 0xb5, 0x2f, 0xfd]),
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2 => Some(b"BZh"),
+            #[cfg(feature = "xz")]
+            Self::Xz => Some(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]),
+        }
+    }
+
+    /// Wraps `writer` into the encoder of the format.
+    #[cfg_attr(
+        not(any(feature = "gzip", feature = "zstd", feature = "bzip2", feature = "xz")),
+        allow(clippy::unnecessary_wraps)
+    )]
+    pub(crate) fn encoder<W: Write>(self, writer: W) -> io::Result<Encoder<W>> {
+        Ok(match self {
+            Self::None => Encoder::Plain(writer),
+            #[cfg(feature = "gzip")]
+            Self::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+                writer,
+                flate2::Compression::default(),
+            )),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Encoder::Zstd(zstd::Encoder::new(writer, 0)?),
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2 => Encoder::Bzip2(bzip2::write::BzEncoder::new(
+                writer,
+                bzip2::Compression::default(),
+            )),
+            #[cfg(feature = "xz")]
+            Self::Xz => Encoder::Xz(xz2::write::XzEncoder::new(writer, 6)),
+        })
+    }
+
+    /// Wraps `reader` into the decoder of the format.
+    #[cfg_attr(
+        not(any(feature = "gzip", feature = "zstd", feature = "bzip2", feature = "xz")),
+        allow(clippy::unnecessary_wraps)
+    )]
+    fn decoder<R: BufRead>(self, reader: R) -> io::Result<Decoder<R>> {
+        Ok(match self {
+            Self::None => Decoder::Plain(reader),
+            #[cfg(feature = "gzip")]
+            Self::Gzip => Decoder::Gzip(flate2::bufread::MultiGzDecoder::new(reader)),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Decoder::Zstd(zstd::Decoder::with_buffer(reader)?),
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2 => Decoder::Bzip2(bzip2::bufread::MultiBzDecoder::new(reader)),
+            #[cfg(feature = "xz")]
+            Self::Xz => Decoder::Xz(xz2::bufread::XzDecoder::new_multi_decoder(reader)),
+        })
+    }
+}
+
+/// Decompresses the `reader` with the [`Compression`] detected by its [magic bytes](Compression::from_magic_bytes).
+///
+/// An uncompressed `reader` is returned as is.
+///
+/// # Errors
+///
+/// Returns `Err` when the `reader` can't be read.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use zsplit::compression::decompress;
+/// use zsplit::prelude::*;
+///
+/// let data = "Hello\nWorld,\n42!";
+/// let mut source = decompress(std::io::BufReader::new(data.as_bytes())).unwrap();
+///
+/// split_round_robin(&mut source, &mut [Destination::buffer(), Destination::buffer()]).unwrap();
+/// ```
+pub fn decompress<'a, R: BufRead + 'a>(mut reader: R) -> io::Result<Box<dyn BufRead + 'a>> {
+    if reader.fill_buf()?.len() >= MAGIC_LEN {
+        let compression = Compression::from_magic_bytes(&mut reader)?;
+        return decompressed(compression, reader);
+    }
+
+    // The buffer is too short for the magic bytes, so they are read and put in front again.
+    let mut start = Vec::with_capacity(MAGIC_LEN);
+    (&mut reader)
+        .take(u64::try_from(MAGIC_LEN).unwrap_or(u64::MAX))
+        .read_to_end(&mut start)?;
+    let compression = Compression::from_start(&start);
+
+    decompressed(compression, io::Cursor::new(start).chain(reader))
+}
+
+/// Decompresses the `reader` with the `compression`.
+fn decompressed<'a, R: BufRead + 'a>(
+    compression: Compression,
+    reader: R,
+) -> io::Result<Box<dyn BufRead + 'a>> {
+    if compression == Compression::None {
+        return Ok(Box::new(reader));
+    }
+
+    Ok(Box::new(BufReader::new(compression.decoder(reader)?)))
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::None => "none",
+            #[cfg(feature = "gzip")]
+            Self::Gzip => "gzip",
+            #[cfg(feature = "zstd")]
+            Self::Zstd => "zstd",
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2 => "bzip2",
+            #[cfg(feature = "xz")]
+            Self::Xz => "xz",
+        })
+    }
+}
+
+/// The error of parsing an unknown or disabled [`Compression`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownCompression(String);
+
+impl fmt::Display for UnknownCompression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown or disabled compression `{}`, the enabled ones are: {}",
+            self.0,
+            Compression::ENABLED
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+impl std::error::Error for UnknownCompression {}
+
+impl FromStr for Compression {
+    type Err = UnknownCompression;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ENABLED
+            .iter()
+            .copied()
+            .find(|compression| compression.to_string() == name)
+            .ok_or_else(|| UnknownCompression(name.to_owned()))
+    }
+}
+
+/// A [`Write`] which compresses into the inner writer.
+pub(crate) enum Encoder<W: Write> {
+    Plain(W),
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<W>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Encoder<'static, W>),
+    #[cfg(feature = "bzip2")]
+    Bzip2(bzip2::write::BzEncoder<W>),
+    #[cfg(feature = "xz")]
+    Xz(xz2::write::XzEncoder<W>),
+}
+
+impl<W: Write> Encoder<W> {
+    /// Writes the trailer of the format and returns the inner writer.
+    #[cfg_attr(
+        not(any(feature = "gzip", feature = "zstd", feature = "bzip2", feature = "xz")),
+        allow(clippy::unnecessary_wraps)
+    )]
+    pub(crate) fn finish(self) -> io::Result<W> {
+        match self {
+            Self::Plain(writer) => Ok(writer),
+            #[cfg(feature = "gzip")]
+            Self::Gzip(encoder) => encoder.finish(),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(encoder) => encoder.finish(),
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2(encoder) => encoder.finish(),
+            #[cfg(feature = "xz")]
+            Self::Xz(encoder) => encoder.finish(),
+        }
+    }
+
+    /// The inner writer.
+    pub(crate) fn get_ref(&self) -> &W {
+        match self {
+            Self::Plain(writer) => writer,
+            #[cfg(feature = "gzip")]
+            Self::Gzip(encoder) => encoder.get_ref(),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(encoder) => encoder.get_ref(),
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2(encoder) => encoder.get_ref(),
+            #[cfg(feature = "xz")]
+            Self::Xz(encoder) => encoder.get_ref(),
+        }
+    }
+
+    fn as_write(&mut self) -> &mut dyn Write {
+        match self {
+            Self::Plain(writer) => writer,
+            #[cfg(feature = "gzip")]
+            Self::Gzip(encoder) => encoder,
+            #[cfg(feature = "zstd")]
+            Self::Zstd(encoder) => encoder,
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2(encoder) => encoder,
+            #[cfg(feature = "xz")]
+            Self::Xz(encoder) => encoder,
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.as_write().write(buf)
+    }
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.as_write().flush()
+    }
+}
+
+/// A [`Read`] which decompresses the inner reader.
+enum Decoder<R: BufRead> {
+    Plain(R),
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::bufread::MultiGzDecoder<R>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Decoder<'static, R>),
+    #[cfg(feature = "bzip2")]
+    Bzip2(bzip2::bufread::MultiBzDecoder<R>),
+    #[cfg(feature = "xz")]
+    Xz(xz2::bufread::XzDecoder<R>),
+}
+
+impl<R: BufRead> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(reader) => reader.read(buf),
+            #[cfg(feature = "gzip")]
+            Self::Gzip(decoder) => decoder.read(buf),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(decoder) => decoder.read(buf),
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2(decoder) => decoder.read(buf),
+            #[cfg(feature = "xz")]
+            Self::Xz(decoder) => decoder.read(buf),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use super::*;
+
+#[test]
+fn no_extension_is_none() {
+    assert_eq!(Compression::from_path("a"), Compression::None);
+}
+
+#[test]
+fn unknown_extension_is_none() {
+    assert_eq!(Compression::from_path("a.txt"), Compression::None);
+}
+
+#[test]
+fn parse_displayed() {
+    for compression in Compression::ENABLED {
+        assert_eq!(compression.to_string().parse(), Ok(*compression));
+    }
+}
+
+#[test]
+fn parse_unknown() {
+    assert!("rar".parse::<Compression>().is_err());
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn gzip_extension() {
+    assert_eq!(Compression::from_path("a.txt.gz"), Compression::Gzip);
+    assert_eq!(Compression::Gzip.extension(), Some("gz"));
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd_extension() {
+    assert_eq!(Compression::from_path("a.txt.zst"), Compression::Zstd);
+    assert_eq!(Compression::Zstd.extension(), Some("zst"));
+}
+
+#[cfg(feature = "bzip2")]
+#[test]
+fn bzip2_extension() {
+    assert_eq!(Compression::from_path("a.txt.bz2"), Compression::Bzip2);
+    assert_eq!(Compression::Bzip2.extension(), Some("bz2"));
+}
+
+#[cfg(feature = "xz")]
+#[test]
+fn xz_extension() {
+    assert_eq!(Compression::from_path("a.txt.xz"), Compression::Xz);
+    assert_eq!(Compression::Xz.extension(), Some("xz"));
+}
+
+#[test]
+fn empty_is_none() -> io::Result<()> {
+    assert_eq!(
+        Compression::from_magic_bytes(&mut io::empty())?,
+        Compression::None
+    );
+    Ok(())
+}
+
+#[test]
+fn short_is_none() -> io::Result<()> {
+    let mut decompressed = String::new();
+    decompress(BufReader::with_capacity(1, "ab".as_bytes()))?.read_to_string(&mut decompressed)?;
+
+    assert_eq!(decompressed, "ab");
+    Ok(())
+}
+
+#[cfg(feature = "bzip2")]
+#[test]
+fn bzip2_requires_block_size() {
+    assert_eq!(Compression::from_start(b"BZh9"), Compression::Bzip2);
+    assert_eq!(Compression::from_start(b"BZh0"), Compression::None);
+    assert_eq!(Compression::from_start(b"BZhello"), Compression::None);
+}
+
+#[test]
+fn round_trip() -> io::Result<()> {
+    for compression in Compression::ENABLED {
+        let mut encoder = compression.encoder(Vec::new())?;
+        encoder.write_all(b"Hello\nWorld\n")?;
+        let compressed = encoder.finish()?;
+
+        let mut source = compressed.as_slice();
+        assert_eq!(Compression::from_magic_bytes(&mut source)?, *compression);
+
+        let mut decompressed = String::new();
+        decompress(source)?.read_to_string(&mut decompressed)?;
+
+        assert_eq!(decompressed, "Hello\nWorld\n");
+    }
+    Ok(())
+}
+
+#[test]
+fn decompress_short_buffers() -> io::Result<()> {
+    for compression in Compression::ENABLED {
+        let mut encoder = compression.encoder(Vec::new())?;
+        encoder.write_all(b"Hello\nWorld\n")?;
+        let compressed = encoder.finish()?;
+
+        let mut decompressed = String::new();
+        decompress(BufReader::with_capacity(1, compressed.as_slice()))?
+            .read_to_string(&mut decompressed)?;
+
+        assert_eq!(decompressed, "Hello\nWorld\n");
+    }
+    Ok(())
+}
@@ -0,0 +1,324 @@
+//! Dropping the duplicate records of a source.
+
+use ahash::{AHashSet, RandomState};
+use std::borrow::BorrowMut;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, BufRead, Read, Write};
+use std::num::NonZeroUsize;
+
+#[cfg(test)]
+#[path = "./dedup_test.rs"]
+mod dedup_test;
+
+/// The count of bits of the Bloom filter, which are set for every record.
+const BLOOM_HASHES: u64 = 7;
+
+/// How the records, which appeared before, are remembered.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use std::num::NonZeroUsize;
+/// use zsplit::dedup::{DuplicateFilter, SeenRecords};
+///
+/// let mut seen = SeenRecords::new(DuplicateFilter::Bloom(NonZeroUsize::new(1024).unwrap()));
+///
+/// assert!(seen.insert(b"a\n"));
+/// assert!(!seen.insert(b"a\r\n"));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DuplicateFilter {
+    /// Remembers a 128-bit hash of every distinct record.
+    ///
+    /// The memory grows with the count of distinct records.
+    #[default]
+    Exact,
+
+    /// Remembers the records in a Bloom filter of this count of bytes.
+    ///
+    /// The memory is bounded, but a distinct record could be taken for a duplicate.
+    /// About 1% of the distinct records are, when their count reaches a tenth of the bits.
+    Bloom(NonZeroUsize),
+}
+
+/// The records, which appeared before.
+///
+/// The records are compared without their line ending.
+#[derive(Debug, Clone)]
+pub struct SeenRecords {
+    remembered: Remembered,
+    hasher: RandomState,
+    duplicates: u64,
+}
+
+#[derive(Debug, Clone)]
+enum Remembered {
+    Hashes(AHashSet<u128>),
+    Bloom(Vec<u64>),
+}
+
+impl SeenRecords {
+    /// Creates [`SeenRecords`] without any record.
+    #[must_use]
+    pub fn new(filter: DuplicateFilter) -> Self {
+        let remembered = match filter {
+            DuplicateFilter::Exact => Remembered::Hashes(AHashSet::new()),
+            DuplicateFilter::Bloom(bytes) => Remembered::Bloom(vec![0; (bytes.get() / 8).max(1)]),
+        };
+
+        Self {
+            remembered,
+            hasher: RandomState::new(),
+            duplicates: 0,
+        }
+    }
+
+    /// Remembers the `record` and returns, whether it's new.
+    pub fn insert(&mut self, record: &[u8]) -> bool {
+        let hash = hash(&self.hasher, content(record));
+        let new = match &mut self.remembered {
+            Remembered::Hashes(hashes) => hashes.insert(hash),
+            Remembered::Bloom(bits) => insert_bloom(bits, hash),
+        };
+
+        self.duplicates += u64::from(!new);
+        new
+    }
+
+    /// The count of duplicates, which were inserted.
+    #[must_use]
+    #[inline]
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+}
+
+/// Sets the bits of the `hash` in the Bloom filter and returns, whether any of them wasn't set.
+fn insert_bloom(bits: &mut [u64], hash: u128) -> bool {
+    let len = u64::try_from(bits.len())
+        .unwrap_or(u64::MAX)
+        .saturating_mul(64);
+    let (first, second) = split_hash(hash);
+    let mut new = false;
+
+    for index in 0..BLOOM_HASHES {
+        let bit = first.wrapping_add(index.wrapping_mul(second)) % len;
+        let word = &mut bits[usize::try_from(bit / 64).unwrap_or_default()];
+        let mask = 1 << (bit % 64);
+
+        new |= *word & mask == 0;
+        *word |= mask;
+    }
+    new
+}
+
+/// Splits the `hash` into two 64-bit hashes, where the second one is odd.
+fn split_hash(hash: u128) -> (u64, u64) {
+    let bytes = hash.to_le_bytes();
+    let (first, second) = bytes.split_at(8);
+    let hash = |bytes: &[u8]| u64::from_le_bytes(<[u8; 8]>::try_from(bytes).unwrap_or_default());
+
+    (hash(first), hash(second) | 1)
+}
+
+/// The 128-bit hash of a `record` combined of two independent 64-bit hashes by the `hasher`.
+fn hash(hasher: &RandomState, record: &[u8]) -> u128 {
+    let hash = |seed: u8| {
+        let mut hasher = hasher.build_hasher();
+        hasher.write_u8(seed);
+        hasher.write(record);
+        hasher.finish()
+    };
+
+    u128::from(hash(0)) << 64 | u128::from(hash(1))
+}
+
+/// The `record` without its line ending.
+fn content(record: &[u8]) -> &[u8] {
+    match record.strip_suffix(b"\n") {
+        Some(record) => record.strip_suffix(b"\r").unwrap_or(record),
+        None => record,
+    }
+}
+
+/// A source, which contains only the first appearance of every record.
+///
+/// The duplicates are dropped or written to a separate sink.
+/// The [`SeenRecords`] could be borrowed, so the records are unique across multiple sources.
+/// A record is collected in memory until it's complete, so its length should be limited by
+/// [`LimitedRecords`](crate::limit::LimitedRecords) before.
+/// The positions of errors during splitting refer to the unique records.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use zsplit::dedup::{DuplicateFilter, SeenRecords, UniqueRecords};
+/// use zsplit::prelude::*;
+///
+/// let mut seen = SeenRecords::new(DuplicateFilter::Exact);
+/// let mut duplicates = Vec::new();
+/// let mut source =
+///     UniqueRecords::new("a\nb\na\n".as_bytes(), &mut seen).duplicates(&mut duplicates);
+/// let mut destinations = [Destination::buffer()];
+///
+/// split_round_robin(&mut source, &mut destinations).unwrap();
+///
+/// let [destination] = destinations;
+/// assert_eq!(destination.into_utf8_string().unwrap(), "a\nb\n");
+/// assert_eq!(duplicates, b"a\n");
+/// assert_eq!(seen.duplicates(), 1);
+/// ```
+#[derive(Debug)]
+pub struct UniqueRecords<R, S = SeenRecords, W = io::Sink> {
+    source: R,
+    seen: S,
+    duplicates: W,
+    /// The unique records, which weren't completely read yet.
+    unique: Vec<u8>,
+    read: usize,
+    /// The start of a record, which isn't complete in the buffer of the source.
+    pending: Vec<u8>,
+}
+
+impl<R: BufRead, S: BorrowMut<SeenRecords>> UniqueRecords<R, S> {
+    /// Drops the records of `source`, which are already in `seen`.
+    #[inline]
+    pub fn new(source: R, seen: S) -> Self {
+        Self {
+            source,
+            seen,
+            duplicates: io::sink(),
+            unique: Vec::new(),
+            read: 0,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<R, S, W> UniqueRecords<R, S, W> {
+    /// Writes the duplicates to `duplicates` instead of dropping them.
+    #[must_use]
+    #[inline]
+    pub fn duplicates<D: Write>(self, duplicates: D) -> UniqueRecords<R, S, D> {
+        UniqueRecords {
+            source: self.source,
+            seen: self.seen,
+            duplicates,
+            unique: self.unique,
+            read: self.read,
+            pending: self.pending,
+        }
+    }
+
+    /// Consumes the [`UniqueRecords`], returning the source.
+    #[must_use]
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+}
+
+impl<R: BufRead, S: BorrowMut<SeenRecords>, W: Write> UniqueRecords<R, S, W> {
+    /// Filters the next buffer of the source and returns, whether it wasn't exhausted.
+    fn filter_buffer(&mut self) -> io::Result<bool> {
+        let mut router = Router {
+            seen: self.seen.borrow_mut(),
+            unique: &mut self.unique,
+            duplicates: &mut self.duplicates,
+        };
+        let buffer = self.source.fill_buf()?;
+        if buffer.is_empty() {
+            router.pending(&mut self.pending)?;
+            return Ok(false);
+        }
+
+        let complete = memchr::memrchr(b'\n', buffer).map_or(0, |end| end + 1);
+        let (records, rest) = buffer.split_at(complete);
+        router.records(records, &mut self.pending)?;
+        self.pending.extend_from_slice(rest);
+
+        let len = buffer.len();
+        self.source.consume(len);
+        Ok(true)
+    }
+}
+
+/// Routes the records to the unique records or the duplicates.
+struct Router<'a, W> {
+    seen: &'a mut SeenRecords,
+    unique: &'a mut Vec<u8>,
+    duplicates: &'a mut W,
+}
+
+impl<W: Write> Router<'_, W> {
+    /// Filters the complete `records`, where the first one starts with `pending`.
+    fn records(&mut self, records: &[u8], pending: &mut Vec<u8>) -> io::Result<()> {
+        let mut start = 0;
+
+        if !pending.is_empty() && !records.is_empty() {
+            start = memchr::memchr(b'\n', records).map_or(records.len(), |end| end + 1);
+            pending.extend_from_slice(&records[..start]);
+            self.pending(pending)?;
+        }
+
+        let first = start;
+        for end in memchr::memchr_iter(b'\n', &records[first..]) {
+            let end = first + end + 1;
+            self.record(&records[start..end])?;
+            start = end;
+        }
+        Ok(())
+    }
+
+    /// Filters the `pending` record, if any.
+    fn pending(&mut self, pending: &mut Vec<u8>) -> io::Result<()> {
+        if !pending.is_empty() {
+            self.record(pending)?;
+            pending.clear();
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, record: &[u8]) -> io::Result<()> {
+        if self.seen.insert(record) {
+            self.unique.extend_from_slice(record);
+            Ok(())
+        } else {
+            self.duplicates.write_all(record)
+        }
+    }
+}
+
+impl<R: BufRead, S: BorrowMut<SeenRecords>, W: Write> Read for UniqueRecords<R, S, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: BufRead, S: BorrowMut<SeenRecords>, W: Write> BufRead for UniqueRecords<R, S, W> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.read == self.unique.len() {
+            self.unique.clear();
+            self.read = 0;
+
+            if !self.filter_buffer()? {
+                break;
+            }
+        }
+        Ok(&self.unique[self.read..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.read = (self.read + amt).min(self.unique.len());
+    }
+}
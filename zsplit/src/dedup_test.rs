@@ -0,0 +1,76 @@
+use super::*;
+
+/// Reads the unique records of the `source` with a buffer of `capacity` bytes, returning them
+/// with the duplicates.
+fn read(source: &str, capacity: usize, filter: DuplicateFilter) -> io::Result<(String, Vec<u8>)> {
+    let source = io::BufReader::with_capacity(capacity, source.as_bytes());
+    let mut duplicates = Vec::new();
+    let mut read = String::new();
+
+    UniqueRecords::new(source, SeenRecords::new(filter))
+        .duplicates(&mut duplicates)
+        .read_to_string(&mut read)?;
+    Ok((read, duplicates))
+}
+
+fn bloom(bytes: usize) -> DuplicateFilter {
+    NonZeroUsize::new(bytes).map_or(DuplicateFilter::Exact, DuplicateFilter::Bloom)
+}
+
+#[test]
+fn drop_duplicates() {
+    let read = read("a\nb\na\nc\nb\n", 64, DuplicateFilter::Exact);
+
+    assert_eq!(
+        read.ok(),
+        Some(("a\nb\nc\n".to_owned(), b"a\nb\n".to_vec()))
+    );
+}
+
+#[test]
+fn records_exceeding_the_buffer() {
+    let read = read("long\nlong\nlonger\nlong", 3, DuplicateFilter::Exact);
+
+    assert_eq!(
+        read.ok(),
+        Some(("long\nlonger\n".to_owned(), b"long\nlong".to_vec()))
+    );
+}
+
+#[test]
+fn ignore_line_endings() {
+    let read = read("a\r\na\nb\nb", 64, DuplicateFilter::Exact);
+
+    assert_eq!(read.ok(), Some(("a\r\nb\n".to_owned(), b"a\nb".to_vec())));
+}
+
+#[test]
+fn unique_across_sources() -> io::Result<()> {
+    let mut seen = SeenRecords::new(DuplicateFilter::Exact);
+    let mut read = String::new();
+
+    UniqueRecords::new("a\nb\n".as_bytes(), &mut seen).read_to_string(&mut read)?;
+    UniqueRecords::new("b\nc\n".as_bytes(), &mut seen).read_to_string(&mut read)?;
+
+    assert_eq!(read, "a\nb\nc\n");
+    assert_eq!(seen.duplicates(), 1);
+    Ok(())
+}
+
+#[test]
+fn bloom_filter() {
+    let source = (0..100)
+        .flat_map(|record| [record.to_string(), "\n".to_owned()])
+        .collect::<String>();
+    let read = read(&source.repeat(2), 64, bloom(1024));
+
+    assert_eq!(read.ok(), Some((source.clone(), source.into_bytes())));
+}
+
+#[test]
+fn tiny_bloom_filter() {
+    let mut seen = SeenRecords::new(bloom(1));
+
+    assert!(seen.insert(b"a\n"));
+    assert!(!seen.insert(b"a\n"));
+}
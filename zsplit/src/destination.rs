@@ -1,5 +1,10 @@
 //! Destination for splitting.
 
+use crate::compression::Compression;
+use crate::file::{FileOptions, Staged};
+use crate::flush::FlushPolicy;
+use crate::records::Run;
+use crate::transform::{self, Transform, Transformer, Written};
 use io::Write;
 use std::io;
 use std::path::Path;
@@ -18,6 +23,12 @@ pub struct Destination<S: Write> {
 
     /// Where the splitting operation writes data to.
     pub sink: S,
+
+    /// When the sink is flushed during the splitting operation.
+    pub flush_policy: FlushPolicy,
+
+    /// How the records are transformed before they are written to the sink.
+    pub transform: Option<Transformer>,
 }
 
 impl<S: Write> Destination<S> {
@@ -37,6 +48,8 @@ impl<S: Write> Destination<S> {
         Self {
             assigned_lines,
             sink,
+            flush_policy: FlushPolicy::default(),
+            transform: None,
         }
     }
 
@@ -57,6 +70,71 @@ impl<S: Write> Destination<S> {
         Self {
             sink,
             assigned_lines: 1,
+            flush_policy: FlushPolicy::default(),
+            transform: None,
+        }
+    }
+
+    /// Sets when the sink is flushed during the splitting operation.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use zsplit::flush::FlushPolicy;
+    /// use zsplit::prelude::*;
+    ///
+    /// let destination = Destination::new_with_sink(std::io::sink()).flush_policy(FlushPolicy::EveryRecord);
+    /// assert_eq!(destination.flush_policy, FlushPolicy::EveryRecord);
+    /// ```
+    #[must_use]
+    #[inline]
+    pub fn flush_policy(mut self, flush_policy: FlushPolicy) -> Self {
+        self.flush_policy = flush_policy;
+        self
+    }
+
+    /// Transforms every record with `transform` before it's written to the sink.
+    ///
+    /// The [`SplitReport`](crate::report::SplitReport) and checkpoints count the bytes of the
+    /// transformed records, which are written to the sink.
+    /// A record exceeding the buffer of the source is collected in memory, as the transform needs
+    /// the complete record.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use zsplit::prelude::*;
+    /// use zsplit::transform::Record;
+    ///
+    /// let mut source = "alice 1\nbob 2\n".as_bytes();
+    /// let mut destinations = [Destination::buffer().transform(
+    ///     |record: &Record<'_>, output: &mut String| {
+    ///         output.push_str(record.line().split(' ').next().unwrap_or_default());
+    ///     },
+    /// )];
+    ///
+    /// split_round_robin(&mut source, &mut destinations).unwrap();
+    ///
+    /// let [destination] = destinations;
+    /// assert_eq!(destination.into_utf8_string().unwrap(), "alice\nbob\n");
+    /// ```
+    #[must_use]
+    #[inline]
+    pub fn transform<T: Transform + 'static>(mut self, transform: T) -> Self {
+        self.transform = Some(Transformer::new(transform));
+        self
+    }
+
+    /// Writes the records of the `run` after its first `written` bytes, so a repetition doesn't
+    /// write any byte twice.
+    pub(crate) fn write_run(&mut self, run: &Run<'_>, written: &mut Written) -> io::Result<()> {
+        match &mut self.transform {
+            Some(transformer) => transformer.write(&mut self.sink, run, written),
+            None => write_untransformed(&mut self.sink, run, written),
         }
     }
 
@@ -165,7 +243,9 @@ where
 {
     /// Creates a buffered [`std::fs::File`] and turns it into a [`Destination`] with `1` as a default for `assigned_lines`.
     ///
-    /// The `sink` is of type [`std::io::BufWriter<File>`].
+    /// The `sink` is of type [`FileSink`](crate::file::FileSink) and never compressed, so it's
+    /// complete even without being [finished](Destination::finish).
+    /// [`Destination::new_with_path_and_options`] compresses it by the extension of the `path`.
     ///
     /// # Errors
     ///
@@ -190,14 +270,13 @@ where
     /// assert!(Destination::new_with_path(".").is_err());
     /// ```
     pub fn new_with_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
-        let sink = Self::create_sink(path)?;
-
-        Ok(Self::new_with_sink(sink))
+        Self::new_with_path_and_lines(path, 1)
     }
 
     /// Creates a buffered [`std::fs::File`] and turns it into a [`Destination`].
     ///
-    /// The `sink` is of type [`std::io::BufWriter<File>`].
+    /// The `sink` is of type [`FileSink`](crate::file::FileSink) and never compressed like the one
+    /// of [`Destination::new_with_path`].
     ///
     /// # Errors
     ///
@@ -216,29 +295,158 @@ where
         path: P,
         assigned_lines: usize,
     ) -> io::Result<Self> {
-        let sink = Self::create_sink(path)?;
+        let options = FileOptions::new().compression(Compression::None);
+
+        Self::new_with_path_and_options(path, assigned_lines, &options)
+    }
+
+    /// Creates a [`std::fs::File`] with [`FileOptions`] and turns it into a [`Destination`].
+    ///
+    /// The `sink` is of type [`FileSink`](crate::file::FileSink), which has to be
+    /// [finished](Destination::finish), when it's compressed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` when the `File` can't be created.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use zsplit::file::FileOptions;
+    /// use zsplit::prelude::*;
+    ///
+    /// Destination::new_with_path_and_options("a.txt", 42, &FileOptions::new()).unwrap();
+    /// ```
+    pub fn new_with_path_and_options<P: AsRef<Path>>(
+        path: P,
+        assigned_lines: usize,
+        options: &FileOptions,
+    ) -> io::Result<Self> {
+        let sink = Self::create_sink(path, options)?;
 
         Ok(Self::new(sink, assigned_lines))
     }
 }
 
+impl<S: Finish> Destination<S> {
+    /// Consumes the [`Destination`] and [finishes](Finish::finish) the `sink`.
+    ///
+    /// This has to be called after the splitting operation to write the trailer of a compressed file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` when the remaining data can't be written.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use zsplit::prelude::*;
+    ///
+    /// Destination::new_with_sink(std::io::sink()).finish().unwrap();
+    /// ```
+    #[inline]
+    pub fn finish(self) -> io::Result<()> {
+        self.sink.finish()
+    }
+
+    /// Finishes the [`Destination`] like [`Finish::finish_staged`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` when the remaining data can't be written to the sink.
+    #[inline]
+    pub fn finish_staged(self) -> io::Result<Staged> {
+        self.sink.finish_staged()
+    }
+}
+
+/// Writes the records of the `run` as they are after its first `written` bytes.
+fn write_untransformed<W: Write>(
+    sink: &mut W,
+    run: &Run<'_>,
+    written: &mut Written,
+) -> io::Result<()> {
+    let result = transform::write_remainder(sink, run.as_str().as_bytes(), &mut written.source);
+    written.sink = if result.is_ok() {
+        run.as_str().len()
+    } else {
+        let mut rest = *run;
+        rest.take_within(written.source).as_str().len()
+    };
+    result
+}
+
+/// A `sink` which has to be finished after the splitting operation.
+///
+/// Unlike dropping it, finishing reports the errors of writing the remaining data.
+pub trait Finish: Write {
+    /// Writes the remaining data and flushes the `sink`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` when the remaining data can't be written.
+    fn finish(self) -> io::Result<()>;
+
+    /// Writes the remaining data like [`Finish::finish`], but defers replacing the files of an
+    /// [atomic](FileOptions::atomic) sink to [`Staged::persist`].
+    ///
+    /// So multiple sinks replace their files only after all of them were finished.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` when the remaining data can't be written.
+    #[inline]
+    fn finish_staged(self) -> io::Result<Staged>
+    where
+        Self: Sized,
+    {
+        self.finish().map(|()| Staged::default())
+    }
+}
+
+impl Finish for Vec<u8> {
+    #[inline]
+    fn finish(self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Finish for io::Sink {
+    #[inline]
+    fn finish(self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<W: Write> Finish for io::BufWriter<W> {
+    #[inline]
+    fn finish(self) -> io::Result<()> {
+        self.into_inner()
+            .map(drop)
+            .map_err(io::IntoInnerError::into_error)
+    }
+}
+
 #[doc(hidden)]
 pub trait SinkFromPath {
     type Sink: Write;
-    fn create_sink<P: AsRef<Path>>(path: P) -> io::Result<Self::Sink>;
+    fn create_sink<P: AsRef<Path>>(path: P, options: &FileOptions) -> io::Result<Self::Sink>;
 }
 
 #[cfg(not(any(test, feature = "test_mock")))]
 mod production {
-    use super::{io, Destination, Path, SinkFromPath};
-    use io::BufWriter;
-    use std::fs::File;
+    use super::{io, Destination, FileOptions, Path, SinkFromPath};
+    use crate::file::FileSink;
 
-    impl SinkFromPath for Destination<BufWriter<File>> {
-        type Sink = BufWriter<File>;
+    impl SinkFromPath for Destination<FileSink> {
+        type Sink = FileSink;
         #[inline]
-        fn create_sink<P: AsRef<Path>>(path: P) -> io::Result<Self::Sink> {
-            File::create(path).map(BufWriter::new)
+        fn create_sink<P: AsRef<Path>>(path: P, options: &FileOptions) -> io::Result<Self::Sink> {
+            options.open(path)
         }
     }
 }
@@ -1,9 +1,9 @@
-use super::*;
+use super::{io, Destination, FileOptions, Path, SinkFromPath};
 
 impl SinkFromPath for Destination<io::Sink> {
     type Sink = io::Sink;
 
-    fn create_sink<P: AsRef<Path>>(path: P) -> io::Result<Self::Sink> {
+    fn create_sink<P: AsRef<Path>>(path: P, _options: &FileOptions) -> io::Result<Self::Sink> {
         let path = path
             .as_ref()
             .to_str()
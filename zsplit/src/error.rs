@@ -0,0 +1,195 @@
+//! The error of a splitting operation.
+
+use std::fmt;
+use std::io;
+
+#[cfg(test)]
+#[path = "./error_test.rs"]
+mod error_test;
+
+/// The operation, which failed during splitting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Operation {
+    /// Reading a record from the source.
+    Read,
+
+    /// Creating the sink of a destination.
+    Create,
+
+    /// Writing or flushing a destination.
+    Write,
+
+    /// Saving a [`Checkpoint`](crate::checkpoint::Checkpoint).
+    Checkpoint,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Read => "read",
+            Self::Create => "create",
+            Self::Write => "write",
+            Self::Checkpoint => "checkpoint",
+        })
+    }
+}
+
+/// The position of a record in the source.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Position {
+    pub(crate) record: u64,
+    pub(crate) offset: u64,
+}
+
+impl Position {
+    /// The position behind `records` records of `len` bytes.
+    pub(crate) fn advanced(self, records: u64, len: usize) -> Self {
+        Self {
+            record: self.record + records,
+            offset: self.offset + u64::try_from(len).unwrap_or(u64::MAX),
+        }
+    }
+}
+
+/// An IO-error during splitting with the location, where it happened.
+///
+/// The position refers to the source passed to the splitting function.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use zsplit::error::Operation;
+/// use zsplit::prelude::*;
+///
+/// let mut source = "0\n1\n2\n".as_bytes();
+/// let mut buffer = [0_u8; 3];
+/// let mut destinations = [Destination::new(&mut buffer[..], 1)];
+///
+/// let error = split_round_robin(&mut source, &mut destinations).unwrap_err();
+///
+/// assert_eq!(error.operation(), Operation::Write);
+/// assert_eq!(error.destination(), Some(0));
+/// assert_eq!(error.record(), 1);
+/// assert_eq!(error.offset(), 2);
+/// ```
+#[derive(Debug)]
+pub struct Error {
+    operation: Operation,
+    destination: Option<usize>,
+    position: Position,
+    source: io::Error,
+}
+
+impl Error {
+    pub(crate) fn read(position: Position, source: io::Error) -> Self {
+        Self {
+            operation: Operation::Read,
+            destination: None,
+            position,
+            source,
+        }
+    }
+
+    pub(crate) fn create(position: Position, destination: usize, source: io::Error) -> Self {
+        Self {
+            operation: Operation::Create,
+            destination: Some(destination),
+            position,
+            source,
+        }
+    }
+
+    pub(crate) fn write(position: Position, destination: Option<usize>, source: io::Error) -> Self {
+        Self {
+            operation: Operation::Write,
+            destination,
+            position,
+            source,
+        }
+    }
+
+    pub(crate) fn checkpoint(position: Position, source: io::Error) -> Self {
+        Self {
+            operation: Operation::Checkpoint,
+            destination: None,
+            position,
+            source,
+        }
+    }
+
+    /// The failed operation.
+    #[must_use]
+    #[inline]
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    /// The index of the affected destination.
+    ///
+    /// It is `None` for reading errors and when there was no destination left.
+    #[must_use]
+    #[inline]
+    pub fn destination(&self) -> Option<usize> {
+        self.destination
+    }
+
+    /// The number of the affected record, starting at 0.
+    #[must_use]
+    #[inline]
+    pub fn record(&self) -> u64 {
+        self.position.record
+    }
+
+    /// The byte offset of the start of the affected record in the source.
+    #[must_use]
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.position.offset
+    }
+
+    /// The kind of the underlying IO-error.
+    #[must_use]
+    #[inline]
+    pub fn kind(&self) -> io::ErrorKind {
+        self.source.kind()
+    }
+
+    /// Consumes the [`Error`], returning the underlying IO-error.
+    #[must_use]
+    #[inline]
+    pub fn into_io_error(self) -> io::Error {
+        self.source
+    }
+}
+
+/// A [`std::result::Result`] of a splitting operation.
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Couldn't {} record {} at byte {} of the source",
+            self.operation, self.position.record, self.position.offset
+        )?;
+        if let Some(destination) = self.destination {
+            write!(f, " for destination {destination}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(error: Error) -> Self {
+        Self::new(error.kind(), error)
+    }
+}
@@ -0,0 +1,41 @@
+use super::*;
+
+#[test]
+fn advance_position() {
+    let position = Position::default().advanced(1, 3).advanced(2, 5);
+
+    assert_eq!(
+        position,
+        Position {
+            record: 3,
+            offset: 8
+        }
+    );
+}
+
+#[test]
+fn display_location() {
+    let position = Position {
+        record: 4,
+        offset: 10,
+    };
+
+    assert_eq!(
+        Error::write(position, Some(1), io::ErrorKind::BrokenPipe.into()).to_string(),
+        "Couldn't write record 4 at byte 10 of the source for destination 1"
+    );
+    assert_eq!(
+        Error::read(position, io::ErrorKind::InvalidData.into()).to_string(),
+        "Couldn't read record 4 at byte 10 of the source"
+    );
+}
+
+#[test]
+fn into_io_error_keeps_kind() {
+    let error = io::Error::from(Error::read(
+        Position::default(),
+        io::ErrorKind::InvalidData.into(),
+    ));
+
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
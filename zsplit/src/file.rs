@@ -0,0 +1,421 @@
+//! Files as sinks of path based destinations.
+
+use crate::compression::{self, Compression, Encoder};
+use crate::destination::Finish;
+use crate::rotation::{Rotation, Rotator};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+#[path = "./file_test.rs"]
+mod file_test;
+
+/// What happens with an already existing file of a path based [`Destination`](crate::Destination).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WritePolicy {
+    /// Truncates the existing file.
+    #[default]
+    Overwrite,
+    /// Appends to the existing file.
+    Append,
+    /// Fails with [`io::ErrorKind::AlreadyExists`].
+    FailIfExists,
+}
+
+/// Options for creating the file of a path based [`Destination`](crate::Destination).
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```no_run
+/// use zsplit::file::FileOptions;
+/// use zsplit::prelude::*;
+///
+/// let options = FileOptions::new();
+///
+/// Destination::new_with_path_and_options("a.txt", 42, &options).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FileOptions {
+    compression: Option<Compression>,
+    create_parents: bool,
+    atomic: bool,
+    policy: WritePolicy,
+    rotation: Option<Rotation>,
+}
+
+impl FileOptions {
+    /// Creates the default [`FileOptions`].
+    ///
+    /// The [`Compression`] is detected by the extension of the path.
+    #[must_use]
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses `compression` for every file instead of detecting it by the extension.
+    #[must_use]
+    #[inline]
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Creates the missing parent directories of the file.
+    #[must_use]
+    #[inline]
+    pub fn create_parents(mut self, create_parents: bool) -> Self {
+        self.create_parents = create_parents;
+        self
+    }
+
+    /// Writes into a sibling temporary file, which replaces the file at the path only when the
+    /// [`FileSink`] is [finished](Finish::finish).
+    ///
+    /// The temporary file is removed, when the [`FileSink`] is dropped without being finished.
+    /// Therefore a failed splitting operation never leaves a truncated file behind.
+    #[must_use]
+    #[inline]
+    pub fn atomic(mut self, atomic: bool) -> Self {
+        self.atomic = atomic;
+        self
+    }
+
+    /// Defines how an already existing file is treated.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use zsplit::file::{FileOptions, WritePolicy};
+    /// use zsplit::prelude::*;
+    ///
+    /// let options = FileOptions::new().policy(WritePolicy::FailIfExists);
+    ///
+    /// Destination::new_with_path_and_options("a.txt", 42, &options).unwrap();
+    /// ```
+    #[must_use]
+    #[inline]
+    pub fn policy(mut self, policy: WritePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Rotates the file, when it reaches the limits of the [`Rotation`].
+    #[must_use]
+    #[inline]
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = Some(rotation);
+        self
+    }
+
+    /// Creates the file at `path` with these options.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` when the `File` or its parent directories can't be created or the file
+    /// already exists with [`WritePolicy::FailIfExists`].
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> io::Result<FileSink> {
+        let path = path.as_ref();
+
+        // Rotated files keep the compression of the first file.
+        let compression = self.compression_of(path);
+        let options = self.compression(compression);
+
+        let mut sink = FileSink {
+            file: options.open_file(path)?,
+            options,
+            compression,
+            rotator: self.rotation.map(|rotation| Rotator::new(rotation, path)),
+            rotated: Staged::default(),
+        };
+        sink.count_existing(path)?;
+        Ok(sink)
+    }
+
+    /// The [`Compression`] of the file at `path`.
+    pub(crate) fn compression_of(&self, path: &Path) -> Compression {
+        self.compression
+            .unwrap_or_else(|| Compression::from_path(path))
+    }
+
+    /// Whether the file is written into a temporary file first.
+    pub(crate) fn is_atomic(&self) -> bool {
+        self.atomic
+    }
+
+    /// Creates the file at `path` with the already determined [`Compression`].
+    fn open_file(self, path: &Path) -> io::Result<OpenFile> {
+        if let Some(parent) = path.parent().filter(|_| self.create_parents) {
+            fs::create_dir_all(parent)?;
+        }
+
+        let replace = self.policy != WritePolicy::FailIfExists;
+        let temporary = self.atomic.then(|| TemporaryFile::new(path, replace));
+        let file_path = temporary.as_ref().map_or(path, |temporary| &temporary.path);
+
+        let encoder = self
+            .compression
+            .unwrap_or_default()
+            .encoder(self.create_file(path, file_path)?)?;
+
+        Ok(OpenFile {
+            writer: BufWriter::new(encoder),
+            temporary,
+        })
+    }
+
+    /// Creates the file at `file_path`, which is a temporary file in the atomic mode, for `path`.
+    fn create_file(self, path: &Path, file_path: &Path) -> io::Result<File> {
+        let is_temporary = path != file_path;
+
+        match self.policy {
+            WritePolicy::Overwrite => File::create(file_path),
+            WritePolicy::Append => {
+                if is_temporary {
+                    copy_existing(path, file_path)?;
+                }
+                OpenOptions::new().append(true).create(true).open(file_path)
+            }
+            WritePolicy::FailIfExists => {
+                if is_temporary && path.exists() {
+                    return Err(io::ErrorKind::AlreadyExists.into());
+                }
+                OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(file_path)
+            }
+        }
+    }
+}
+
+/// Copies the file at `from`, if it exists.
+fn copy_existing(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::copy(from, to) {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result.map(drop),
+    }
+}
+
+/// A buffered and optionally compressed and rotated [`File`].
+///
+/// It has to be [finished](Finish::finish) to write the trailer of the [`Compression`].
+#[derive(Debug)]
+pub struct FileSink {
+    file: OpenFile,
+    options: FileOptions,
+    compression: Compression,
+    rotator: Option<Rotator>,
+    /// The finished files before the current one, which are persisted together with it.
+    rotated: Staged,
+}
+
+impl FileSink {
+    /// The [`Compression`] of the file.
+    #[must_use]
+    #[inline]
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Finishes the current file and continues with the file at `path`.
+    ///
+    /// In the atomic mode, the finished file replaces its target only with the last one.
+    fn rotate(&mut self, path: &Path) -> io::Result<()> {
+        let next = self.options.open_file(path)?;
+
+        let finished = std::mem::replace(&mut self.file, next).stage()?;
+        self.rotated.append(finished);
+        self.count_existing(path)
+    }
+
+    /// Counts the existing content of the file at `path`, which is appended, towards the limits
+    /// of the rotation.
+    fn count_existing(&mut self, path: &Path) -> io::Result<()> {
+        let rotator = match &mut self.rotator {
+            Some(rotator) if self.options.policy == WritePolicy::Append => rotator,
+            _ => return Ok(()),
+        };
+
+        match File::open(path) {
+            Ok(file) => rotator.count(compression::decompress(io::BufReader::new(file))?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+impl Write for FileSink {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let rotator = match &mut self.rotator {
+            Some(rotator) => rotator,
+            None => return self.file.writer.write(buf),
+        };
+
+        // A rotation only happens at a record boundary, so records are written one by one.
+        let chunk = Rotator::chunk(buf);
+        let mut next = rotator.rotate(chunk);
+        // An appended file could already reach the limits.
+        while let Some(path) = next {
+            self.rotate(&path)?;
+            next = self
+                .rotator
+                .as_mut()
+                .and_then(|rotator| rotator.rotate(chunk));
+        }
+
+        let written = self.file.writer.write(chunk)?;
+        if let Some(rotator) = &mut self.rotator {
+            rotator.update(&chunk[..written]);
+        }
+        Ok(written)
+    }
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.writer.flush()
+    }
+}
+
+impl Finish for FileSink {
+    #[inline]
+    fn finish(self) -> io::Result<()> {
+        self.finish_staged()?.persist()
+    }
+
+    fn finish_staged(self) -> io::Result<Staged> {
+        let mut staged = self.rotated;
+        staged.append(self.file.stage()?);
+
+        if let Some(rotator) = self
+            .rotator
+            .filter(|_| self.options.policy == WritePolicy::Overwrite)
+        {
+            staged.stale = rotator.stale();
+        }
+        Ok(staged)
+    }
+}
+
+/// The currently written file of a [`FileSink`].
+struct OpenFile {
+    writer: BufWriter<Encoder<File>>,
+    temporary: Option<TemporaryFile>,
+}
+
+impl OpenFile {
+    /// Writes the remaining data and syncs a temporary file, which isn't persisted yet.
+    fn stage(self) -> io::Result<Staged> {
+        let mut file = self
+            .writer
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)?
+            .finish()?;
+
+        file.flush()?;
+
+        if self.temporary.is_some() {
+            file.sync_all()?;
+        }
+        Ok(Staged {
+            temporaries: self.temporary.into_iter().collect(),
+            stale: Vec::new(),
+        })
+    }
+}
+
+impl fmt::Debug for OpenFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OpenFile")
+            .field("file", self.writer.get_ref().get_ref())
+            .field("temporary", &self.temporary)
+            .finish()
+    }
+}
+
+/// The temporary files of a [finished](Finish::finish_staged) sink, which replace their targets,
+/// when they are persisted.
+///
+/// They are removed, when [`Staged`] is dropped without being persisted.
+#[derive(Debug, Default)]
+#[must_use]
+pub struct Staged {
+    temporaries: Vec<TemporaryFile>,
+    /// The files left by an earlier [`Rotation`], which are removed after persisting.
+    stale: Vec<PathBuf>,
+}
+
+impl Staged {
+    /// Renames the temporary files to their targets.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` when a temporary file can't be renamed or a stale file of a [`Rotation`]
+    /// can't be removed.
+    pub fn persist(self) -> io::Result<()> {
+        self.temporaries
+            .into_iter()
+            .try_for_each(TemporaryFile::persist)?;
+        self.stale.iter().try_for_each(fs::remove_file)
+    }
+
+    fn append(&mut self, other: Self) {
+        self.temporaries.extend(other.temporaries);
+        self.stale.extend(other.stale);
+    }
+}
+
+/// A sibling of the `target` file, which is removed when dropped before being persisted.
+#[derive(Debug)]
+struct TemporaryFile {
+    path: PathBuf,
+    target: PathBuf,
+    /// Whether an existing `target` is replaced.
+    replace: bool,
+    persisted: bool,
+}
+
+impl TemporaryFile {
+    fn new(target: &Path, replace: bool) -> Self {
+        let mut file_name = std::ffi::OsString::from(".");
+        file_name.push(target.file_name().unwrap_or_default());
+        file_name.push(format!(".{}.tmp", std::process::id()));
+
+        Self {
+            path: target.with_file_name(file_name),
+            target: target.to_owned(),
+            replace,
+            persisted: false,
+        }
+    }
+
+    /// Renames the temporary file to the `target`.
+    ///
+    /// Without replacing, the temporary file is linked to the `target` instead, which fails
+    /// atomically with [`io::ErrorKind::AlreadyExists`], and the temporary file is removed.
+    fn persist(mut self) -> io::Result<()> {
+        if self.replace {
+            fs::rename(&self.path, &self.target)?;
+            self.persisted = true;
+        } else {
+            fs::hard_link(&self.path, &self.target)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for TemporaryFile {
+    fn drop(&mut self) {
+        if !self.persisted {
+            // The temporary file is incomplete and a failing removal can't be handled anyway.
+            drop(fs::remove_file(&self.path));
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use super::*;
+
+struct TestDir(PathBuf);
+
+impl TestDir {
+    fn new(name: &str) -> io::Result<Self> {
+        let path = std::env::temp_dir().join(format!("zsplit_file_{name}_{}", std::process::id()));
+        fs::create_dir_all(&path)?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        drop(fs::remove_dir_all(&self.0));
+    }
+}
+
+#[test]
+fn persist_staged_files() -> io::Result<()> {
+    let dir = TestDir::new("persist_staged_files")?;
+    let options = FileOptions::new().atomic(true);
+    let [a, b] = ["a", "b"].map(|name| dir.0.join(name));
+
+    let mut sink = options.open(&a)?;
+    sink.write_all(b"0\n")?;
+    let staged = sink.finish_staged()?;
+    assert!(!a.exists());
+
+    options.open(&b)?.finish_staged()?.persist()?;
+    staged.persist()?;
+    assert_eq!(fs::read_to_string(&a)?, "0\n");
+    assert!(b.exists());
+    Ok(())
+}
+
+#[test]
+fn remove_dropped_staged_files() -> io::Result<()> {
+    let dir = TestDir::new("remove_dropped_staged_files")?;
+    let path = dir.0.join("a");
+
+    drop(
+        FileOptions::new()
+            .atomic(true)
+            .open(&path)?
+            .finish_staged()?,
+    );
+
+    assert_eq!(fs::read_dir(&dir.0)?.count(), 0);
+    Ok(())
+}
+
+fn rotated(dir: &TestDir, options: FileOptions, data: &[u8]) -> io::Result<Staged> {
+    let mut sink = options
+        .rotation(Rotation::new().max_lines(1))
+        .open(dir.0.join("a"))?;
+    sink.write_all(data)?;
+    sink.finish_staged()
+}
+
+#[test]
+fn stage_rotated_atomic_files() -> io::Result<()> {
+    let dir = TestDir::new("stage_rotated_atomic_files")?;
+
+    let staged = rotated(&dir, FileOptions::new().atomic(true), b"0\n1\n")?;
+    assert!(!dir.0.join("a").exists());
+
+    staged.persist()?;
+    assert_eq!(fs::read_to_string(dir.0.join("a"))?, "0\n");
+    assert_eq!(fs::read_to_string(dir.0.join("a.1"))?, "1\n");
+    Ok(())
+}
+
+#[test]
+fn rotate_appended_files() -> io::Result<()> {
+    let dir = TestDir::new("rotate_appended_files")?;
+    fs::write(dir.0.join("a"), "0\n")?;
+    fs::write(dir.0.join("a.1"), "1\n")?;
+
+    let options = FileOptions::new().policy(WritePolicy::Append);
+    rotated(&dir, options, b"2\n")?.persist()?;
+
+    assert_eq!(fs::read_to_string(dir.0.join("a"))?, "0\n");
+    assert_eq!(fs::read_to_string(dir.0.join("a.2"))?, "2\n");
+    Ok(())
+}
+
+#[test]
+fn remove_stale_rotated_files() -> io::Result<()> {
+    let dir = TestDir::new("remove_stale_rotated_files")?;
+    fs::write(dir.0.join("a.2"), "2\n")?;
+    fs::write(dir.0.join("a.3"), "3\n")?;
+
+    rotated(&dir, FileOptions::new(), b"0\n1\n")?.persist()?;
+
+    assert!(dir.0.join("a.1").exists());
+    assert!(!dir.0.join("a.2").exists());
+    assert!(!dir.0.join("a.3").exists());
+    Ok(())
+}
+
+#[test]
+fn keep_file_created_meanwhile() -> io::Result<()> {
+    let dir = TestDir::new("keep_file_created_meanwhile")?;
+    let path = dir.0.join("a");
+    let options = FileOptions::new()
+        .atomic(true)
+        .policy(WritePolicy::FailIfExists);
+
+    let mut sink = options.open(&path)?;
+    sink.write_all(b"new\n")?;
+    fs::write(&path, "meanwhile\n")?;
+    let error = sink.finish().map_err(|error| error.kind());
+
+    assert_eq!(error, Err(io::ErrorKind::AlreadyExists));
+    assert_eq!(fs::read_to_string(&path)?, "meanwhile\n");
+    assert_eq!(fs::read_dir(&dir.0)?.count(), 1);
+    Ok(())
+}
@@ -0,0 +1,173 @@
+//! Flushing of destinations during splitting.
+
+use std::fmt;
+use std::num::NonZeroU64;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+#[path = "./flush_test.rs"]
+mod flush_test;
+
+/// When a destination is flushed during splitting.
+///
+/// Every destination is flushed at the end of the splitting regardless of its policy.
+/// The policies are checked, whenever consecutive records are written to the destination at once,
+/// so they are never flushed in between.
+/// An [`Interval`](FlushPolicy::Interval) is also checked, whenever records are written to any
+/// other destination.
+///
+/// It can be parsed from `end`, `record`, a count of records like `100` or an interval in
+/// milliseconds like `250ms`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use zsplit::flush::FlushPolicy;
+/// use zsplit::prelude::*;
+///
+/// let destination = Destination::new_with_sink(std::io::stdout())
+///     .flush_policy(FlushPolicy::EveryRecord);
+///
+/// assert_eq!("250ms".parse(), Ok(FlushPolicy::Interval(std::time::Duration::from_millis(250))));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FlushPolicy {
+    /// Flushes only at the end of the splitting.
+    #[default]
+    AtEnd,
+
+    /// Flushes after every record, so a consumer receives it without delay.
+    EveryRecord,
+
+    /// Flushes after this count of records.
+    Records(NonZeroU64),
+
+    /// Flushes with the first record of any destination after this duration since the last
+    /// flush.
+    Interval(Duration),
+}
+
+/// The error of parsing an invalid [`FlushPolicy`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidFlushPolicy(String);
+
+impl fmt::Display for InvalidFlushPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid flush policy `{}`, expected `end`, `record`, a count of records like `100` or milliseconds like `250ms`",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for InvalidFlushPolicy {}
+
+impl FromStr for FlushPolicy {
+    type Err = InvalidFlushPolicy;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        let parsed = match policy {
+            "end" => Some(Self::AtEnd),
+            "record" => Some(Self::EveryRecord),
+            _ => match policy.strip_suffix("ms") {
+                Some(millis) => millis
+                    .parse()
+                    .ok()
+                    .map(Duration::from_millis)
+                    .map(Self::Interval),
+                None => policy.parse().ok().map(Self::Records),
+            },
+        };
+
+        parsed.ok_or_else(|| InvalidFlushPolicy(policy.to_owned()))
+    }
+}
+
+/// The records written to every destination since its last flush.
+#[derive(Debug, Default)]
+pub(crate) struct Unflushed {
+    pending: Vec<Pending>,
+    /// The earliest deadline of the destinations with a [`FlushPolicy::Interval`].
+    deadline: Option<Instant>,
+}
+
+impl Unflushed {
+    /// Counts `records` records written to the destination at `index` and returns, whether the
+    /// `policy` requires a flush.
+    pub(crate) fn record(&mut self, index: usize, records: u64, policy: FlushPolicy) -> bool {
+        if self.pending.len() <= index {
+            self.pending.resize_with(index + 1, Pending::new);
+        }
+        let pending = &mut self.pending[index];
+        let due = pending.record(records, policy);
+
+        self.deadline = earliest(self.deadline, pending.deadline);
+        due
+    }
+
+    /// The indices of the destinations, whose interval elapsed since their last flush, which are
+    /// considered as flushed.
+    pub(crate) fn overdue(&mut self) -> Vec<usize> {
+        let now = Instant::now();
+        if self.deadline.map_or(true, |deadline| deadline > now) {
+            return Vec::new();
+        }
+        self.deadline = None;
+
+        let mut overdue = Vec::new();
+        for (index, pending) in self.pending.iter_mut().enumerate() {
+            if pending.deadline.map_or(false, |deadline| deadline <= now) {
+                *pending = Pending::new();
+                overdue.push(index);
+            }
+            self.deadline = earliest(self.deadline, pending.deadline);
+        }
+        overdue
+    }
+}
+
+/// The earlier one of the deadlines `a` and `b`.
+fn earliest(a: Option<Instant>, b: Option<Instant>) -> Option<Instant> {
+    a.into_iter().chain(b).min()
+}
+
+#[derive(Debug)]
+struct Pending {
+    records: u64,
+    since: Instant,
+    /// When the unflushed records of a [`FlushPolicy::Interval`] are due.
+    deadline: Option<Instant>,
+}
+
+impl Pending {
+    fn new() -> Self {
+        Self {
+            records: 0,
+            since: Instant::now(),
+            deadline: None,
+        }
+    }
+
+    fn record(&mut self, records: u64, policy: FlushPolicy) -> bool {
+        self.records += records;
+
+        let due = match policy {
+            FlushPolicy::AtEnd => false,
+            FlushPolicy::EveryRecord => true,
+            FlushPolicy::Records(records) => self.records >= records.get(),
+            FlushPolicy::Interval(interval) => {
+                self.deadline = Some(self.since + interval);
+                self.since.elapsed() >= interval
+            }
+        };
+        if due {
+            *self = Self::new();
+        }
+        due
+    }
+}
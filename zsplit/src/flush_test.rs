@@ -0,0 +1,62 @@
+use super::*;
+
+#[test]
+fn parse_policies() {
+    assert_eq!("end".parse(), Ok(FlushPolicy::AtEnd));
+    assert_eq!("record".parse(), Ok(FlushPolicy::EveryRecord));
+    assert_eq!(
+        "100".parse().ok(),
+        NonZeroU64::new(100).map(FlushPolicy::Records)
+    );
+    assert_eq!(
+        "250ms".parse(),
+        Ok(FlushPolicy::Interval(Duration::from_millis(250)))
+    );
+}
+
+#[test]
+fn parse_invalid_policies() {
+    for policy in ["", "0", "-1", "ms", "1s", "records"] {
+        assert_eq!(
+            policy.parse::<FlushPolicy>(),
+            Err(InvalidFlushPolicy(policy.to_owned()))
+        );
+    }
+}
+
+#[test]
+fn unflushed_records() {
+    let mut unflushed = Unflushed::default();
+    let policy = NonZeroU64::new(2).map_or(FlushPolicy::AtEnd, FlushPolicy::Records);
+
+    let due = [1, 0, 1, 1, 1].map(|index| unflushed.record(index, 1, policy));
+
+    assert_eq!(due, [false, false, true, false, true]);
+}
+
+#[test]
+fn unflushed_interval() {
+    let mut unflushed = Unflushed::default();
+
+    assert!(unflushed.record(0, 1, FlushPolicy::Interval(Duration::ZERO)));
+    assert!(!unflushed.record(0, 1, FlushPolicy::Interval(Duration::from_secs(3600))));
+    assert!(!unflushed.record(0, 1, FlushPolicy::AtEnd));
+}
+
+#[test]
+fn overdue_intervals() {
+    let mut unflushed = Unflushed::default();
+    let policies = [
+        FlushPolicy::Interval(Duration::from_millis(10)),
+        FlushPolicy::Interval(Duration::from_secs(3600)),
+        FlushPolicy::AtEnd,
+    ];
+
+    let due = [0, 1, 2].map(|index| unflushed.record(index, 1, policies[index]));
+    let before = unflushed.overdue();
+    std::thread::sleep(Duration::from_millis(20));
+
+    assert_eq!(due, [false; 3]);
+    assert_eq!((before, unflushed.overdue()), (vec![], vec![0]));
+    assert_eq!(unflushed.overdue(), Vec::<usize>::new());
+}
@@ -0,0 +1,155 @@
+//! Sources, which keep growing like log files.
+
+use std::fmt;
+use std::fs::{self, File, Metadata};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(test)]
+#[path = "./follow_test.rs"]
+mod follow_test;
+
+/// The default [`Follow::poll_interval`].
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Reads a file from its start and waits for new data at its end like `tail -f`, so it never
+/// reaches EOF.
+///
+/// A truncated file is read again from its start.
+/// When the path refers to another file, for example after a log rotation, the new file is read
+/// from its start.
+/// On Unix a replaced file is detected by its inode, elsewhere only by a truncation.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```no_run
+/// use zsplit::follow::Follow;
+/// use zsplit::prelude::*;
+///
+/// let mut source = Follow::open("app.log").unwrap();
+/// let mut destinations = [Destination::new_with_path("a.log").unwrap()];
+///
+/// // Runs until an error occurs.
+/// split_round_robin(&mut source, &mut destinations).unwrap_err();
+/// ```
+pub struct Follow {
+    path: PathBuf,
+    reader: BufReader<File>,
+    poll_interval: Duration,
+    on_idle: Option<Box<dyn FnMut() -> io::Result<()>>>,
+}
+
+impl Follow {
+    /// Opens the file at `path` for following it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` when the file can't be opened.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+
+        Ok(Self {
+            reader: BufReader::new(File::open(&path)?),
+            path,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            on_idle: None,
+        })
+    }
+
+    /// How long to wait for new data at the end of the file.
+    ///
+    /// It defaults to 250 milliseconds.
+    #[must_use]
+    #[inline]
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Calls `on_idle` every time before waiting for new data.
+    ///
+    /// This could flush the destinations, so their consumers receive the records without delay.
+    #[must_use]
+    #[inline]
+    pub fn on_idle<F>(mut self, on_idle: F) -> Self
+    where
+        F: FnMut() -> io::Result<()> + 'static,
+    {
+        self.on_idle = Some(Box::new(on_idle));
+        self
+    }
+
+    /// Waits for new data and reopens the file, when it was truncated or replaced.
+    fn wait(&mut self) -> io::Result<()> {
+        if let Some(on_idle) = &mut self.on_idle {
+            on_idle()?;
+        }
+        thread::sleep(self.poll_interval);
+
+        let current = match fs::metadata(&self.path) {
+            Ok(current) => current,
+            // The file was moved away and its successor isn't created yet.
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(error),
+        };
+
+        if !is_same_file(&self.reader.get_ref().metadata()?, &current) {
+            self.reader = BufReader::new(File::open(&self.path)?);
+        } else if current.len() < self.reader.stream_position()? {
+            self.reader.seek(SeekFrom::Start(0))?;
+        }
+        Ok(())
+    }
+}
+
+impl Read for Follow {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl BufRead for Follow {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.reader.fill_buf()?.is_empty() {
+            self.wait()?;
+        }
+        self.reader.fill_buf()
+    }
+
+    #[inline]
+    fn consume(&mut self, amt: usize) {
+        self.reader.consume(amt);
+    }
+}
+
+impl fmt::Debug for Follow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Follow")
+            .field("path", &self.path)
+            .field("reader", &self.reader)
+            .field("poll_interval", &self.poll_interval)
+            .field("on_idle", &self.on_idle.is_some())
+            .finish()
+    }
+}
+
+#[cfg(unix)]
+fn is_same_file(file: &Metadata, other: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    file.dev() == other.dev() && file.ino() == other.ino()
+}
+
+#[cfg(not(unix))]
+fn is_same_file(_file: &Metadata, _other: &Metadata) -> bool {
+    true
+}
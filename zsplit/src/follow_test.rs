@@ -0,0 +1,74 @@
+use super::*;
+use std::io::Write;
+
+struct TestDir(PathBuf);
+
+impl TestDir {
+    fn new(name: &str) -> io::Result<Self> {
+        let path = std::env::temp_dir().join(format!("zsplit_{name}_{}", std::process::id()));
+        fs::create_dir_all(&path)?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        drop(fs::remove_dir_all(&self.0));
+    }
+}
+
+/// Follows the file `log` in `dir`, which is changed by `change` on the first idle call.
+fn follow(
+    dir: &TestDir,
+    content: &str,
+    change: impl FnOnce(&Path) -> io::Result<()> + 'static,
+) -> io::Result<Follow> {
+    let path = dir.0.join("log");
+    fs::write(&path, content)?;
+
+    let mut change = Some(change);
+    let changed = path.clone();
+    Ok(Follow::open(&path)?
+        .poll_interval(Duration::from_millis(1))
+        .on_idle(move || change.take().map_or(Ok(()), |change| change(&changed))))
+}
+
+fn lines(follow: &mut Follow, count: usize) -> io::Result<Vec<String>> {
+    follow.lines().take(count).collect()
+}
+
+#[test]
+fn appended_data() -> io::Result<()> {
+    let dir = TestDir::new("follow_appended_data")?;
+    let mut follow = follow(&dir, "0\n", |path| {
+        fs::OpenOptions::new()
+            .append(true)
+            .open(path)?
+            .write_all(b"1\n")
+    })?;
+
+    assert_eq!(lines(&mut follow, 2)?, ["0", "1"]);
+    Ok(())
+}
+
+#[test]
+fn truncated_file() -> io::Result<()> {
+    let dir = TestDir::new("follow_truncated_file")?;
+    let mut follow = follow(&dir, "0\n1\n", |path| fs::write(path, "2\n"))?;
+
+    assert_eq!(lines(&mut follow, 3)?, ["0", "1", "2"]);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn rotated_file() -> io::Result<()> {
+    let dir = TestDir::new("follow_rotated_file")?;
+    let mut follow = follow(&dir, "0\n", |path| {
+        fs::rename(path, path.with_extension("1"))?;
+        fs::write(path, "1\n")
+    })?;
+
+    assert_eq!(lines(&mut follow, 2)?, ["0", "1"]);
+    Ok(())
+}
@@ -0,0 +1,161 @@
+//! Destinations created on demand for the keys of records.
+
+use crate::flush::FlushPolicy;
+use crate::transform::{Transform, Transformer};
+use crate::Destination;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+#[cfg(test)]
+#[path = "./keyed_test.rs"]
+mod keyed_test;
+
+/// Destinations, which are created for every new key of a record by
+/// [`split_by_key`](crate::split::by_key).
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use zsplit::keyed::KeyedDestinations;
+/// use zsplit::prelude::*;
+///
+/// let mut source = "a 0\nb 1\na 2\n".as_bytes();
+/// let mut destinations = KeyedDestinations::new().max_keys(64);
+///
+/// split_by_key(
+///     &mut source,
+///     &mut destinations,
+///     |line| line.split(' ').next().unwrap_or_default(),
+///     |_key| Ok(Vec::new()),
+/// )
+/// .unwrap();
+///
+/// let destinations = destinations.into_inner();
+/// assert_eq!(destinations[0].0, "a");
+/// assert_eq!(destinations[0].1.sink, b"a 0\na 2\n");
+/// assert_eq!(destinations[1].0, "b");
+/// ```
+#[derive(Debug)]
+pub struct KeyedDestinations<S: Write> {
+    indices: BTreeMap<String, usize>,
+    destinations: Vec<(String, Destination<S>)>,
+    max_keys: Option<usize>,
+    flush_policy: FlushPolicy,
+    transform: Option<Transformer>,
+}
+
+impl<S: Write> KeyedDestinations<S> {
+    /// Creates [`KeyedDestinations`] without any destination.
+    #[must_use]
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            indices: BTreeMap::new(),
+            destinations: Vec::new(),
+            max_keys: None,
+            flush_policy: FlushPolicy::default(),
+            transform: None,
+        }
+    }
+
+    /// Limits the count of distinct keys.
+    ///
+    /// Splitting fails, when a record has an additional key.
+    #[must_use]
+    #[inline]
+    pub fn max_keys(mut self, max_keys: usize) -> Self {
+        self.max_keys = Some(max_keys);
+        self
+    }
+
+    /// Sets the [`FlushPolicy`] of the created destinations.
+    #[must_use]
+    #[inline]
+    pub fn flush_policy(mut self, flush_policy: FlushPolicy) -> Self {
+        self.flush_policy = flush_policy;
+        self
+    }
+
+    /// Transforms the records of the created destinations like [`Destination::transform`].
+    #[must_use]
+    #[inline]
+    pub fn transform<T: Transform + 'static>(mut self, transform: T) -> Self {
+        self.transform = Some(Transformer::new(transform));
+        self
+    }
+
+    /// The count of distinct keys.
+    #[must_use]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.destinations.len()
+    }
+
+    /// Whether there are no keys yet.
+    #[must_use]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.destinations.is_empty()
+    }
+
+    /// The keys in the order of their first appearance.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.destinations.iter().map(|(key, _)| key.as_str())
+    }
+
+    /// Consumes the [`KeyedDestinations`], returning the keys with their destinations in the
+    /// order of their first appearance.
+    #[must_use]
+    #[inline]
+    pub fn into_inner(self) -> Vec<(String, Destination<S>)> {
+        self.destinations
+    }
+
+    /// The index of the destination of `key`, which is created on its first appearance.
+    pub(crate) fn index<C>(&mut self, key: &str, create: &mut C) -> io::Result<usize>
+    where
+        C: FnMut(&str) -> io::Result<S>,
+    {
+        if let Some(index) = self.indices.get(key) {
+            return Ok(*index);
+        }
+
+        if let Some(max_keys) = self.max_keys.filter(|max_keys| self.len() >= *max_keys) {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("There are more distinct keys than {max_keys}"),
+            ));
+        }
+
+        let index = self.len();
+        let mut destination =
+            Destination::new_with_sink(create(key)?).flush_policy(self.flush_policy);
+        destination.transform.clone_from(&self.transform);
+        self.destinations.push((key.to_owned(), destination));
+        self.indices.insert(key.to_owned(), index);
+        Ok(index)
+    }
+
+    pub(crate) fn destination_mut(&mut self, index: usize) -> &mut Destination<S> {
+        &mut self.destinations[index].1
+    }
+
+    /// Flushes every destination, failing with the index of the first failing one.
+    pub(crate) fn flush(&mut self) -> Result<(), (usize, io::Error)> {
+        self.destinations
+            .iter_mut()
+            .enumerate()
+            .try_for_each(|(index, (_, destination))| {
+                destination.flush().map_err(|error| (index, error))
+            })
+    }
+}
+
+impl<S: Write> Default for KeyedDestinations<S> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
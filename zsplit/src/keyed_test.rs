@@ -0,0 +1,49 @@
+use super::*;
+
+fn buffers() -> impl FnMut(&str) -> io::Result<Vec<u8>> {
+    |_key| Ok(Vec::new())
+}
+
+#[test]
+fn same_key_same_destination() {
+    let mut destinations = KeyedDestinations::new();
+    let mut create = buffers();
+
+    assert_eq!(destinations.index("a", &mut create).ok(), Some(0));
+    assert_eq!(destinations.index("b", &mut create).ok(), Some(1));
+    assert_eq!(destinations.index("a", &mut create).ok(), Some(0));
+    assert_eq!(destinations.len(), 2);
+}
+
+#[test]
+fn too_many_keys() {
+    let mut destinations = KeyedDestinations::new().max_keys(1);
+    let mut create = buffers();
+
+    assert_eq!(destinations.index("a", &mut create).ok(), Some(0));
+    assert_eq!(destinations.index("a", &mut create).ok(), Some(0));
+    assert!(destinations.index("b", &mut create).is_err());
+}
+
+#[test]
+fn failing_creation() {
+    let mut destinations = KeyedDestinations::<Vec<u8>>::new();
+
+    assert!(destinations
+        .index("a", &mut |_| Err(io::ErrorKind::PermissionDenied.into()))
+        .is_err());
+    assert!(destinations.is_empty());
+}
+
+#[test]
+fn transform_created_destinations() {
+    let mut destinations = KeyedDestinations::new().transform(
+        |record: &crate::transform::Record<'_>, output: &mut String| {
+            output.push_str(&record.line().to_uppercase());
+        },
+    );
+    let mut create = buffers();
+
+    assert_eq!(destinations.index("a", &mut create).ok(), Some(0));
+    assert!(destinations.destination_mut(0).transform.is_some());
+}
@@ -3,10 +3,20 @@
 //! This crate could be used to send input to multiple threads via [`channel_io`](https://lib.rs/crates/channel_io)
 //! or send it to the `STDIN` of multiple [`std::process::Command`]s.
 //!
-//! # Warnings
+//! # Features
 //!
-//! As a line of [`std::io::BufReader`] has to be completely in memory, an unbounded long line
-//! could lead to a `DoS` vulnerability.
+//! Path based destinations with [`FileOptions`](file::FileOptions) are compressed by the extension
+//! of the path, when the format is enabled.
+//! Sources can be decompressed by their magic bytes with [`compression::decompress`].
+//! See [`compression`] for the available formats.
+//!
+//! # Memory
+//!
+//! A record exceeding the buffer of the source is written in fragments, so an unbounded long line
+//! doesn't have to be completely in memory.
+//! [`limit::LimitedRecords`] limits the length of the records by failing or truncating.
+//! [`dedup::UniqueRecords`] remembers a hash of every distinct record, unless it's bounded by a
+//! Bloom filter.
 //!
 //! # Examples
 //!
@@ -74,14 +84,38 @@
 //! child_2.kill().unwrap();
 //! ```
 
+#![forbid(unsafe_code)]
+
+pub mod checkpoint;
+pub mod compression;
+pub mod dedup;
 pub mod destination;
+pub mod error;
+pub mod file;
+pub mod flush;
+pub mod follow;
+pub mod keyed;
+pub mod limit;
+pub mod policy;
+pub mod pool;
+mod records;
+pub mod report;
+pub mod rotation;
+pub mod select;
 pub mod split;
+pub mod transform;
 
 /// All you need from this Crate.
 pub mod prelude {
     pub use crate::destination::Destination;
+    pub use crate::split::by_key as split_by_key;
+    pub use crate::split::contiguous as split_contiguous;
     pub use crate::split::round_robin as split_round_robin;
+    pub use crate::split::round_robin_resumable as split_round_robin_resumable;
+    pub use crate::split::round_robin_with_policy as split_round_robin_with_policy;
 }
 
+#[doc(inline)]
+pub use error::{Error, Result};
 #[doc(inline)]
 pub use prelude::*;
@@ -0,0 +1,185 @@
+//! Limiting the length of the records of a source.
+
+use std::io::{self, BufRead, Read};
+use std::num::NonZeroUsize;
+
+#[cfg(test)]
+#[path = "./limit_test.rs"]
+mod limit_test;
+
+/// What happens with a record exceeding the maximal length of [`LimitedRecords`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OversizedRecords {
+    /// Fails reading with an [`io::ErrorKind::InvalidData`] error.
+    #[default]
+    Fail,
+
+    /// Drops the bytes after the maximal length up to the line ending.
+    Truncate,
+
+    /// Reads the record completely.
+    ///
+    /// The splitting functions write a record exceeding the buffer of the source in fragments, so
+    /// it's never completely in memory.
+    Stream,
+}
+
+/// A source, whose records have a maximal length.
+///
+/// The length of a record excludes its `\n`.
+/// Together with the bounded buffer of the source, the memory used for reading stays bounded,
+/// even if the source contains an unbounded long line.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use std::num::NonZeroUsize;
+/// use zsplit::limit::{LimitedRecords, OversizedRecords};
+/// use zsplit::prelude::*;
+///
+/// let max_len = NonZeroUsize::new(3).unwrap();
+/// let mut source = LimitedRecords::new("0\n12345\n6\n".as_bytes(), max_len)
+///     .oversized(OversizedRecords::Truncate);
+/// let mut destinations = [Destination::buffer()];
+///
+/// split_round_robin(&mut source, &mut destinations).unwrap();
+///
+/// let [destination] = destinations;
+/// assert_eq!(destination.into_utf8_string().unwrap(), "0\n123\n6\n");
+/// ```
+#[derive(Debug)]
+pub struct LimitedRecords<R> {
+    source: R,
+    max_len: usize,
+    oversized: OversizedRecords,
+    /// The length of the current record, which was already read.
+    record_len: usize,
+    /// Whether the rest of the current record is dropped.
+    truncating: bool,
+}
+
+impl<R: BufRead> LimitedRecords<R> {
+    /// Limits the records of `source` to `max_len` bytes.
+    #[inline]
+    pub fn new(source: R, max_len: NonZeroUsize) -> Self {
+        Self {
+            source,
+            max_len: max_len.get(),
+            oversized: OversizedRecords::default(),
+            record_len: 0,
+            truncating: false,
+        }
+    }
+
+    /// What happens with a record exceeding the maximal length.
+    ///
+    /// It defaults to [`OversizedRecords::Fail`].
+    #[must_use]
+    #[inline]
+    pub fn oversized(mut self, oversized: OversizedRecords) -> Self {
+        self.oversized = oversized;
+        self
+    }
+
+    /// Consumes the [`LimitedRecords`], returning the source.
+    #[must_use]
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+
+    /// Drops the rest of the current record up to its line ending.
+    fn skip_rest(&mut self) -> io::Result<()> {
+        loop {
+            let buffer = self.source.fill_buf()?;
+            let (len, found) = memchr::memchr(b'\n', buffer)
+                .map_or((buffer.len(), buffer.is_empty()), |end| (end, true));
+
+            self.source.consume(len);
+            if found {
+                self.truncating = false;
+                return Ok(());
+            }
+        }
+    }
+
+    fn oversized_error(&self) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "A record exceeds the maximal length of {} bytes",
+                self.max_len
+            ),
+        )
+    }
+}
+
+impl<R: BufRead> Read for LimitedRecords<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: BufRead> BufRead for LimitedRecords<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.oversized == OversizedRecords::Stream {
+            return self.source.fill_buf();
+        }
+
+        let len = loop {
+            if self.truncating {
+                self.skip_rest()?;
+            }
+
+            let buffer = self.source.fill_buf()?;
+            let len = allowed_len(buffer, self.record_len, self.max_len);
+            if len > 0 || buffer.is_empty() {
+                break len;
+            }
+
+            if self.oversized == OversizedRecords::Fail {
+                return Err(self.oversized_error());
+            }
+            self.truncating = true;
+        };
+
+        Ok(&self.source.fill_buf()?[..len])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if amt == 0 {
+            return;
+        }
+        if let Ok(buffer) = self.source.fill_buf() {
+            let consumed = buffer.get(..amt).unwrap_or(buffer);
+            self.record_len = match memchr::memrchr(b'\n', consumed) {
+                Some(end) => consumed.len() - end - 1,
+                None => self.record_len + consumed.len(),
+            };
+        }
+        self.source.consume(amt);
+    }
+}
+
+/// The length of the start of the `buffer`, which doesn't exceed `max_len` bytes per record, when
+/// the current record has already `record_len` bytes.
+fn allowed_len(buffer: &[u8], mut record_len: usize, max_len: usize) -> usize {
+    let mut start = 0;
+
+    for end in memchr::memchr_iter(b'\n', buffer).chain(std::iter::once(buffer.len())) {
+        if record_len + (end - start) > max_len {
+            return start + (max_len - record_len);
+        }
+        record_len = 0;
+        start = end + 1;
+    }
+    buffer.len()
+}
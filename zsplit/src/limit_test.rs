@@ -0,0 +1,57 @@
+use super::*;
+
+/// Reads the `source` with a buffer of `capacity` bytes limited to `max_len` bytes per record.
+fn read(
+    source: &str,
+    capacity: usize,
+    max_len: usize,
+    oversized: OversizedRecords,
+) -> io::Result<String> {
+    let max_len = NonZeroUsize::new(max_len).ok_or(io::ErrorKind::InvalidInput)?;
+    let source = io::BufReader::with_capacity(capacity, source.as_bytes());
+    let mut read = String::new();
+
+    LimitedRecords::new(source, max_len)
+        .oversized(oversized)
+        .read_to_string(&mut read)?;
+    Ok(read)
+}
+
+#[test]
+fn records_within_limit() {
+    let read = read("012\n\n345", 2, 3, OversizedRecords::Fail);
+
+    assert_eq!(read.ok(), Some("012\n\n345".to_owned()));
+}
+
+#[test]
+fn fail_on_oversized_record() {
+    let read = read("012\n3456\n", 2, 3, OversizedRecords::Fail);
+
+    assert_eq!(
+        read.map_err(|error| error.kind()).err(),
+        Some(io::ErrorKind::InvalidData)
+    );
+}
+
+#[test]
+fn truncate_oversized_records() {
+    let read = read("0123456789\n0\n012345", 4, 3, OversizedRecords::Truncate);
+
+    assert_eq!(read.ok(), Some("012\n0\n012".to_owned()));
+}
+
+#[test]
+fn stream_oversized_record() {
+    let read = read("0123456789\n", 4, 3, OversizedRecords::Stream);
+
+    assert_eq!(read.ok(), Some("0123456789\n".to_owned()));
+}
+
+#[test]
+fn allowed_len_of_buffer() {
+    assert_eq!(allowed_len(b"01\n0123", 0, 3), 6);
+    assert_eq!(allowed_len(b"2\n0", 2, 3), 3);
+    assert_eq!(allowed_len(b"23\n", 2, 3), 1);
+    assert_eq!(allowed_len(b"\n", 3, 3), 1);
+}
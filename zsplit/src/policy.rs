@@ -0,0 +1,73 @@
+//! Handling of errors while writing to destinations.
+
+/// What happens, when writing a record to a destination fails.
+///
+/// Interrupted writes are always repeated and don't count as a failure.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use zsplit::policy::ErrorPolicy;
+/// use zsplit::prelude::*;
+///
+/// struct Broken;
+///
+/// impl std::io::Write for Broken {
+///     fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+///         Err(std::io::ErrorKind::BrokenPipe.into())
+///     }
+///
+///     fn flush(&mut self) -> std::io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// let mut source = "0\n1\n2\n".as_bytes();
+/// let mut destinations = [
+///     Destination::new_with_sink(Box::new(Broken) as Box<dyn std::io::Write>),
+///     Destination::new_with_sink(Box::new(Vec::new())),
+/// ];
+///
+/// let report =
+///     split_round_robin_with_policy(&mut source, &mut destinations, ErrorPolicy::Skip).unwrap();
+///
+/// assert_eq!(report.failures[0].index, 0);
+/// assert_eq!(report.destinations[1].records, 3);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorPolicy {
+    /// Shortcircuits the splitting with the error.
+    #[default]
+    FailFast,
+
+    /// Repeats the failed write up to this count of times, before failing.
+    ///
+    /// The first repetition is delayed by 10 ms and every further one twice as long up to a
+    /// second, so a temporary problem could pass.
+    Retry(u32),
+
+    /// Drops the failing destination and redistributes the record and the following ones among the
+    /// remaining destinations.
+    ///
+    /// The failure is part of the [`SplitReport`](crate::report::SplitReport).
+    /// The splitting fails only, when every destination has failed.
+    /// Every run of records is flushed right after writing it, so the records in the buffer of a
+    /// failing destination are redistributed as well.
+    /// A record could be partially written to the dropped destination.
+    /// A destination, which fails after the first fragment of a record exceeding the buffer of the
+    /// source, fails the splitting.
+    Skip,
+}
+
+impl ErrorPolicy {
+    /// The count of repetitions of a failed write.
+    pub(crate) fn retries(self) -> u32 {
+        match self {
+            Self::Retry(retries) => retries,
+            Self::FailFast | Self::Skip => 0,
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! Lazily opened files for a large number of path based destinations.
+
+use crate::compression::Compression;
+use crate::destination::Finish;
+use crate::file::{FileOptions, FileSink, WritePolicy};
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+#[cfg(test)]
+#[path = "./pool_test.rs"]
+mod pool_test;
+
+/// Opens files on demand and keeps at most `max_open` of them open at the same time.
+///
+/// When the limit is reached, the least recently used file is [finished](Finish::finish) and
+/// reopened in [`WritePolicy::Append`] mode on its next write.
+/// This allows more destinations than file descriptors.
+/// As a consequence, a [`Rotation`](crate::rotation::Rotation) restarts with every reopening.
+/// A file, which would be compressed or written [atomically](FileOptions::atomic), can't be
+/// reopened without losing its state, so opening it fails with [`io::ErrorKind::InvalidInput`].
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```no_run
+/// use std::num::NonZeroUsize;
+/// use zsplit::file::FileOptions;
+/// use zsplit::pool::FilePool;
+/// use zsplit::prelude::*;
+///
+/// let max_open = NonZeroUsize::new(64).unwrap();
+/// let pool = FilePool::new(max_open, FileOptions::new());
+///
+/// let mut destinations = (0..1000)
+///     .map(|index| Destination::new_with_sink(pool.sink(format!("{index}.txt"))))
+///     .collect::<Vec<_>>();
+///
+/// let mut source = std::io::BufReader::new("Hello\nWorld,\n42!".as_bytes());
+/// split_round_robin(&mut source, &mut destinations).unwrap();
+///
+/// for destination in destinations {
+///     destination.finish().unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FilePool {
+    state: Rc<RefCell<PoolState>>,
+}
+
+impl FilePool {
+    /// Creates an empty [`FilePool`], which opens the files with `options`.
+    #[must_use]
+    pub fn new(max_open: NonZeroUsize, options: FileOptions) -> Self {
+        Self {
+            state: Rc::new(RefCell::new(PoolState {
+                options,
+                max_open: max_open.get(),
+                open: 0,
+                least_recently_used: None,
+                most_recently_used: None,
+                entries: Vec::new(),
+            })),
+        }
+    }
+
+    /// Creates a sink for the file at `path`, which is opened on the first write.
+    ///
+    /// A file, which was never written, is created when the sink is finished.
+    pub fn sink<P: AsRef<Path>>(&self, path: P) -> PooledFile {
+        let mut state = self.state.borrow_mut();
+        state.entries.push(PoolEntry {
+            path: path.as_ref().to_owned(),
+            file: None,
+            opened: false,
+            previous: None,
+            next: None,
+        });
+
+        PooledFile {
+            state: Rc::clone(&self.state),
+            id: state.entries.len() - 1,
+        }
+    }
+}
+
+/// A file of a [`FilePool`].
+#[derive(Debug)]
+pub struct PooledFile {
+    state: Rc<RefCell<PoolState>>,
+    id: usize,
+}
+
+impl Write for PooledFile {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.state.borrow_mut().file(self.id)?.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut state = self.state.borrow_mut();
+        match &mut state.entries[self.id].file {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Finish for PooledFile {
+    fn finish(self) -> io::Result<()> {
+        self.state.borrow_mut().finish(self.id)
+    }
+}
+
+/// The entries of a [`FilePool`], where the open ones are linked in the order of their last use.
+#[derive(Debug)]
+struct PoolState {
+    options: FileOptions,
+    max_open: usize,
+    open: usize,
+    least_recently_used: Option<usize>,
+    most_recently_used: Option<usize>,
+    entries: Vec<PoolEntry>,
+}
+
+#[derive(Debug)]
+struct PoolEntry {
+    path: PathBuf,
+    file: Option<FileSink>,
+    opened: bool,
+    /// The open entry, which was used before this one.
+    previous: Option<usize>,
+    /// The open entry, which was used after this one.
+    next: Option<usize>,
+}
+
+impl PoolState {
+    /// The open file of the entry `id`.
+    fn file(&mut self, id: usize) -> io::Result<&mut FileSink> {
+        let file = if let Some(file) = self.entries[id].file.take() {
+            self.unlink(id);
+            file
+        } else {
+            if self.open >= self.max_open {
+                self.close_least_recently_used()?;
+            }
+            let file = self.open_entry(id)?;
+            self.open += 1;
+            file
+        };
+
+        self.link(id);
+        Ok(self.entries[id].file.insert(file))
+    }
+
+    /// Opens the file of the entry `id`, which appends to the file written before.
+    fn open_entry(&mut self, id: usize) -> io::Result<FileSink> {
+        let entry = &mut self.entries[id];
+        let options = if entry.opened {
+            self.options.policy(WritePolicy::Append)
+        } else {
+            self.options
+        };
+
+        check_reopenable(&options, &entry.path)?;
+        let file = options.open(&entry.path)?;
+        entry.opened = true;
+        Ok(file)
+    }
+
+    /// Finishes the least recently used file to make room for another one.
+    ///
+    /// An error names the evicted file, as it's reported for the file, which is written.
+    fn close_least_recently_used(&mut self) -> io::Result<()> {
+        let id = match self.least_recently_used {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        self.unlink(id);
+
+        match self.entries[id].file.take() {
+            Some(file) => {
+                self.open -= 1;
+                file.finish()
+                    .map_err(|error| evicted_error(&self.entries[id].path, &error))
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn finish(&mut self, id: usize) -> io::Result<()> {
+        let file = match self.entries[id].file.take() {
+            Some(file) => {
+                self.unlink(id);
+                self.open -= 1;
+                file
+            }
+            None if !self.entries[id].opened => self.open_entry(id)?,
+            None => return Ok(()),
+        };
+        file.finish()
+    }
+
+    /// Appends the entry `id` as the most recently used one.
+    fn link(&mut self, id: usize) {
+        self.entries[id].previous = self.most_recently_used;
+        self.entries[id].next = None;
+
+        match self.most_recently_used {
+            Some(previous) => self.entries[previous].next = Some(id),
+            None => self.least_recently_used = Some(id),
+        }
+        self.most_recently_used = Some(id);
+    }
+
+    /// Removes the open entry `id` from the order of use.
+    fn unlink(&mut self, id: usize) {
+        let PoolEntry { previous, next, .. } = self.entries[id];
+
+        match previous {
+            Some(previous) => self.entries[previous].next = next,
+            None => self.least_recently_used = next,
+        }
+        match next {
+            Some(next) => self.entries[next].previous = previous,
+            None => self.most_recently_used = previous,
+        }
+        self.entries[id].previous = None;
+        self.entries[id].next = None;
+    }
+}
+
+/// Fails, when the file at `path` would lose its state by being finished and reopened.
+fn check_reopenable(options: &FileOptions, path: &Path) -> io::Result<()> {
+    if options.compression_of(path) != Compression::None || options.is_atomic() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "The pooled file `{}` can't be compressed or atomic",
+                path.display()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// The `error` of finishing the evicted file at `path`.
+fn evicted_error(path: &Path, error: &io::Error) -> io::Error {
+    io::Error::new(
+        error.kind(),
+        format!(
+            "Couldn't finish the evicted pooled file `{}`: {error}",
+            path.display()
+        ),
+    )
+}
@@ -0,0 +1,114 @@
+use super::*;
+use crate::Destination;
+use std::fs;
+
+struct TestDir(PathBuf);
+
+impl TestDir {
+    fn new(name: &str) -> io::Result<Self> {
+        let path = std::env::temp_dir().join(format!("zsplit_{name}_{}", std::process::id()));
+        fs::create_dir_all(&path)?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        drop(fs::remove_dir_all(&self.0));
+    }
+}
+
+fn pool(max_open: usize) -> FilePool {
+    FilePool::new(
+        NonZeroUsize::new(max_open).unwrap_or(NonZeroUsize::MIN),
+        FileOptions::new(),
+    )
+}
+
+#[test]
+fn reopen_closed_files() -> io::Result<()> {
+    let dir = TestDir::new("reopen_closed_files")?;
+    let pool = pool(2);
+    let mut destinations = (0..3)
+        .map(|index| Destination::new_with_sink(pool.sink(dir.0.join(index.to_string()))))
+        .collect::<Vec<_>>();
+
+    crate::split_round_robin(&mut "0\n1\n2\n3\n4\n5\n".as_bytes(), &mut destinations)?;
+    assert!(pool.state.borrow().open <= 2);
+
+    destinations.into_iter().try_for_each(Destination::finish)?;
+
+    let contents = (0..3)
+        .map(|index| fs::read_to_string(dir.0.join(index.to_string())))
+        .collect::<io::Result<Vec<_>>>()?;
+    assert_eq!(contents, ["0\n3\n", "1\n4\n", "2\n5\n"]);
+    Ok(())
+}
+
+#[test]
+fn create_unwritten_files() -> io::Result<()> {
+    let dir = TestDir::new("create_unwritten_files")?;
+    let pool = pool(1);
+    let path = dir.0.join("empty");
+
+    pool.sink(&path).finish()?;
+
+    assert_eq!(fs::read_to_string(path)?, "");
+    Ok(())
+}
+
+#[test]
+fn reuse_recently_used_files() -> io::Result<()> {
+    let dir = TestDir::new("reuse_recently_used_files")?;
+    let pool = pool(2);
+    let mut files = (0..3)
+        .map(|index| pool.sink(dir.0.join(index.to_string())))
+        .collect::<Vec<_>>();
+
+    for index in [0, 1, 0, 2, 0] {
+        files[index].write_all(index.to_string().as_bytes())?;
+    }
+    let open = pool
+        .state
+        .borrow()
+        .entries
+        .iter()
+        .map(|entry| entry.file.is_some())
+        .collect::<Vec<_>>();
+    assert_eq!(open, [true, false, true]);
+
+    files.into_iter().try_for_each(Finish::finish)?;
+    assert_eq!(fs::read_to_string(dir.0.join("0"))?, "000");
+    Ok(())
+}
+
+#[test]
+fn reject_atomic_files() -> io::Result<()> {
+    let dir = TestDir::new("reject_atomic_files")?;
+    let pool = FilePool::new(NonZeroUsize::MIN, FileOptions::new().atomic(true));
+    let path = dir.0.join("atomic");
+
+    let error = pool.sink(&path).write_all(b"0\n");
+
+    assert_eq!(
+        error.map_err(|error| error.kind()),
+        Err(io::ErrorKind::InvalidInput)
+    );
+    assert!(!path.exists());
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn name_evicted_file_in_error() -> io::Result<()> {
+    let dir = TestDir::new("name_evicted_file_in_error")?;
+    let pool = pool(1);
+    let mut full = pool.sink("/dev/full");
+    let mut file = pool.sink(dir.0.join("file"));
+
+    full.write_all(b"0\n")?;
+    let error = file.write_all(b"1\n").err().map(|error| error.to_string());
+
+    assert!(error.map_or(false, |error| error.contains("`/dev/full`")));
+    Ok(())
+}
@@ -0,0 +1,325 @@
+//! Scanning the buffer of a source for records without copying them.
+
+use crate::error::{Error, Position};
+use std::io::{self, BufRead};
+
+#[cfg(test)]
+#[path = "./records_test.rs"]
+mod records_test;
+
+/// The length of the line ending `\n`.
+const LF: usize = 1;
+
+/// The length of the line ending `\r\n`.
+const CRLF: usize = 2;
+
+/// The count of bytes of a buffer, after which no more records with `\r\n` are copied at once.
+///
+/// A buffer could be a whole memory mapped file, which mustn't be copied at once.
+const MAX_COPIED: usize = 64 * 1024;
+
+/// Consecutive records of a source, which are terminated by `\n`.
+///
+/// A record exceeding the buffer of the source is split into fragments, which are written in
+/// separate runs.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Run<'a> {
+    records: &'a str,
+    position: Position,
+    /// The length of the line ending of every record in the source, which was replaced by `\n`.
+    ending: usize,
+    /// Whether the run is an unterminated fragment of a record.
+    fragment: bool,
+    /// Whether the first record started in a previous run.
+    continued: bool,
+}
+
+impl<'a> Run<'a> {
+    /// The records as a single slice.
+    pub(crate) fn as_str(&self) -> &'a str {
+        self.records
+    }
+
+    /// The position of the first record.
+    pub(crate) fn position(&self) -> Position {
+        self.position
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Whether the run is an unterminated fragment of a record, whose rest follows in the next
+    /// runs.
+    pub(crate) fn is_fragment(&self) -> bool {
+        self.fragment
+    }
+
+    /// Whether the first record started in a fragment of a previous run, so it belongs to the
+    /// destination of that fragment.
+    pub(crate) fn is_continued(&self) -> bool {
+        self.continued
+    }
+
+    /// Splits off the records, which are completely within the first `len` bytes.
+    pub(crate) fn take_within(&mut self, len: usize) -> Self {
+        let prefix = self.records.as_bytes().get(..len).unwrap_or_default();
+        let records = memchr::memchr_iter(b'\n', prefix).count();
+
+        self.take(u64::try_from(records).unwrap_or(u64::MAX))
+    }
+
+    /// Splits off up to `max` records from the start.
+    ///
+    /// A fragment is taken completely as long as `max` isn't `0`.
+    pub(crate) fn take(&mut self, max: u64) -> Self {
+        let max = usize::try_from(max).unwrap_or(usize::MAX);
+        let (count, len) = memchr::memchr_iter(b'\n', self.records.as_bytes())
+            .take(max)
+            .enumerate()
+            .last()
+            .map_or((0, 0), |(index, end)| (index + 1, end + 1));
+        let len = if self.fragment && max > 0 {
+            self.records.len()
+        } else {
+            len
+        };
+
+        let (taken, records) = self.records.split_at(len);
+        let taken = Self {
+            records: taken,
+            ..*self
+        };
+
+        self.records = records;
+        self.position = advanced(self.position, count, len, self.ending);
+        self.continued &= count == 0;
+        taken
+    }
+}
+
+/// Calls `write` with runs of consecutive records of the `source`, returning the position behind
+/// the last record.
+///
+/// `write` has to take every record of the run.
+/// The positions start at `start`.
+/// The records are separated like [`BufRead::lines`] and always terminated by `\n`.
+/// A run borrows the buffer of the `source`, so only records with `\r\n` or records exceeding the
+/// buffer are copied.
+/// A record exceeding the buffer is written in fragments, so at most two buffers of it are in
+/// memory.
+pub(crate) fn for_each_run(
+    source: &mut dyn BufRead,
+    start: Position,
+    mut write: impl FnMut(&mut Run<'_>) -> Result<(), Error>,
+) -> Result<Position, Error> {
+    let mut position = start;
+    let mut copied = Vec::new();
+
+    loop {
+        let buffer = source
+            .fill_buf()
+            .map_err(|error| Error::read(position, error))?;
+        if buffer.is_empty() {
+            return Ok(position);
+        }
+
+        let len = lf_len(buffer);
+        if len > 0 {
+            position = write_run(&buffer[..len], position, LF, false, &mut write)?;
+            source.consume(len);
+        } else {
+            position = write_copied(source, &mut copied, position, &mut write)?;
+        }
+    }
+}
+
+/// Copies the records at the start of the buffer of the `source`, which can't be borrowed, into
+/// `copied` with `\n` as their line ending and calls `write` with them.
+fn write_copied(
+    source: &mut dyn BufRead,
+    copied: &mut Vec<u8>,
+    position: Position,
+    write: &mut impl FnMut(&mut Run<'_>) -> Result<(), Error>,
+) -> Result<Position, Error> {
+    copied.clear();
+
+    let buffer = source
+        .fill_buf()
+        .map_err(|error| Error::read(position, error))?;
+    let len = copy_crlf(buffer, copied);
+    if len > 0 {
+        source.consume(len);
+        return write_run(copied, position, CRLF, false, write);
+    }
+
+    // The record exceeds the buffer or isn't terminated.
+    write_incomplete(source, copied, position, write)
+}
+
+/// Copies the record at the start of the buffer of the `source`, which isn't complete in it, into
+/// `copied` and calls `write` with it.
+///
+/// When the record doesn't end in the refilled buffer either, it's written in fragments.
+fn write_incomplete(
+    source: &mut dyn BufRead,
+    copied: &mut Vec<u8>,
+    mut position: Position,
+    write: &mut impl FnMut(&mut Run<'_>) -> Result<(), Error>,
+) -> Result<Position, Error> {
+    let mut refilled = false;
+    let mut continued = false;
+
+    loop {
+        let buffer = source
+            .fill_buf()
+            .map_err(|error| Error::read(position, error))?;
+        let (len, complete) = memchr::memchr(b'\n', buffer)
+            .map_or((buffer.len(), buffer.is_empty()), |end| (end + 1, true));
+
+        copied.extend_from_slice(&buffer[..len]);
+        source.consume(len);
+
+        if complete {
+            let ending = terminate(copied);
+            return write_run(copied, position, ending, continued, write);
+        }
+        if refilled {
+            position = write_fragment(copied, position, continued, write)?;
+            continued = true;
+        }
+        refilled = true;
+    }
+}
+
+/// Validates the `records` and calls `write` with them, returning the position behind them.
+fn write_run(
+    records: &[u8],
+    position: Position,
+    ending: usize,
+    continued: bool,
+    write: &mut impl FnMut(&mut Run<'_>) -> Result<(), Error>,
+) -> Result<Position, Error> {
+    let records = std::str::from_utf8(records)
+        .map_err(|error| invalid_utf8(records, position, ending, error.valid_up_to()))?;
+
+    let mut run = Run {
+        records,
+        position,
+        ending,
+        fragment: false,
+        continued,
+    };
+    write(&mut run)?;
+    debug_assert!(run.is_empty(), "Every record of the run has to be written");
+
+    Ok(run.position)
+}
+
+/// Calls `write` with the fragment of a record in `copied` and removes it, returning the position
+/// behind it.
+///
+/// A trailing `\r` or incomplete character stays in `copied` for the next fragment.
+fn write_fragment(
+    copied: &mut Vec<u8>,
+    position: Position,
+    continued: bool,
+    write: &mut impl FnMut(&mut Run<'_>) -> Result<(), Error>,
+) -> Result<Position, Error> {
+    let kept = copied.strip_suffix(b"\r").unwrap_or(copied);
+    let fragment = match std::str::from_utf8(kept) {
+        Err(error) if error.error_len().is_none() => {
+            std::str::from_utf8(&kept[..error.valid_up_to()])
+        }
+        fragment => fragment,
+    }
+    .map_err(|error| invalid_utf8(kept, position, LF, error.valid_up_to()))?;
+
+    let mut run = Run {
+        records: fragment,
+        position,
+        ending: LF,
+        fragment: true,
+        continued,
+    };
+    write(&mut run)?;
+    debug_assert!(run.is_empty(), "The fragment has to be written");
+
+    let (len, position) = (fragment.len(), run.position);
+    copied.drain(..len);
+    Ok(position)
+}
+
+/// The length of the complete records at the start of the `buffer`, which end with `\n` and can
+/// be borrowed as they are.
+fn lf_len(buffer: &[u8]) -> usize {
+    let last = match memchr::memrchr(b'\n', buffer) {
+        Some(last) => last,
+        None => return 0,
+    };
+    let complete = &buffer[..=last];
+
+    let crlf = memchr::memchr_iter(b'\r', complete).find(|index| complete[index + 1] == b'\n');
+    match crlf {
+        Some(crlf) => memchr::memrchr(b'\n', &complete[..crlf]).map_or(0, |end| end + 1),
+        None => complete.len(),
+    }
+}
+
+/// Copies the complete records at the start of the `buffer`, which end with `\r\n`, into `copied`
+/// with `\n` as their line ending and returns their length in the `buffer`.
+///
+/// The copying stops after [`MAX_COPIED`] bytes of the `buffer`.
+fn copy_crlf(buffer: &[u8], copied: &mut Vec<u8>) -> usize {
+    let mut start = 0;
+
+    for end in memchr::memchr_iter(b'\n', buffer) {
+        if end == start || buffer[end - 1] != b'\r' || start >= MAX_COPIED {
+            break;
+        }
+        copied.extend_from_slice(&buffer[start..end - 1]);
+        copied.push(b'\n');
+        start = end + 1;
+    }
+    start
+}
+
+/// The error of the first invalid UTF-8 at `valid_up_to` in `records` at `position`.
+fn invalid_utf8(records: &[u8], position: Position, ending: usize, valid_up_to: usize) -> Error {
+    let valid = &records[..valid_up_to];
+    let start = memchr::memrchr(b'\n', valid).map_or(0, |end| end + 1);
+    let preceding = memchr::memchr_iter(b'\n', valid).count();
+
+    Error::read(
+        advanced(position, preceding, start, ending),
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "stream did not contain valid UTF-8",
+        ),
+    )
+}
+
+/// The position behind `records` records of `len` bytes, whose line endings of `ending` bytes were
+/// replaced by `\n`.
+fn advanced(position: Position, records: usize, len: usize, ending: usize) -> Position {
+    position.advanced(
+        u64::try_from(records).unwrap_or(u64::MAX),
+        len - records * LF + records * ending,
+    )
+}
+
+/// Replaces the line ending of `record` with `\n` and returns the length of the replaced one.
+fn terminate(record: &mut Vec<u8>) -> usize {
+    let mut ending = 0;
+
+    if record.ends_with(b"\n") {
+        record.pop();
+        ending = LF;
+        if record.ends_with(b"\r") {
+            record.pop();
+            ending = CRLF;
+        }
+    }
+    record.push(b'\n');
+    ending
+}
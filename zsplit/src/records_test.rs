@@ -0,0 +1,164 @@
+use super::*;
+
+/// Collects the runs of the `source` read with a buffer of `capacity` bytes.
+fn runs(source: &str, capacity: usize) -> Result<(Vec<String>, Position), Error> {
+    let mut source = io::BufReader::with_capacity(capacity, source.as_bytes());
+    let mut runs = Vec::new();
+
+    let end = for_each_run(&mut source, Position::default(), |run| {
+        runs.push(run.take(u64::MAX).as_str().to_owned());
+        Ok(())
+    })?;
+    Ok((runs, end))
+}
+
+#[test]
+fn borrowed_runs() {
+    let runs = runs("0\n1\n2\n3", 5).map(|(runs, end)| (runs, end.record, end.offset));
+
+    assert_eq!(
+        runs.ok(),
+        Some((
+            vec!["0\n1\n".to_owned(), "2\n".to_owned(), "3\n".to_owned()],
+            4,
+            7
+        ))
+    );
+}
+
+#[test]
+fn records_exceeding_buffer() {
+    let runs = runs("0123456\n7\n", 4).map(|(runs, end)| (runs, end.offset));
+
+    assert_eq!(
+        runs.ok(),
+        Some((vec!["0123456\n".to_owned(), "7\n".to_owned()], 10))
+    );
+}
+
+#[test]
+fn fragments_of_long_record() {
+    let runs = runs("0123456789\n7\n", 4).map(|(runs, end)| (runs, end.record, end.offset));
+
+    assert_eq!(
+        runs.ok(),
+        Some((
+            vec!["01234567".to_owned(), "89\n".to_owned(), "7\n".to_owned()],
+            2,
+            13
+        ))
+    );
+}
+
+#[test]
+fn fragments_keep_line_ending_and_characters() {
+    let runs = runs("012\u{e4}\r\n", 2).map(|(runs, end)| (runs, end.offset));
+
+    assert_eq!(
+        runs.ok(),
+        Some((
+            vec!["012".to_owned(), "\u{e4}".to_owned(), "\n".to_owned()],
+            7
+        ))
+    );
+}
+
+#[test]
+fn flags_of_fragments() -> Result<(), Error> {
+    let mut source = io::BufReader::with_capacity(2, "0123\n4\n".as_bytes());
+    let mut flags = Vec::new();
+
+    for_each_run(&mut source, Position::default(), |run| {
+        flags.push((run.is_fragment(), run.is_continued()));
+        run.take(u64::MAX);
+        Ok(())
+    })?;
+
+    assert_eq!(flags, vec![(true, false), (false, true), (false, false)]);
+    Ok(())
+}
+
+#[test]
+fn crlf_records() {
+    let runs = runs("0\n1\r\n2\n3\r\n", 64).map(|(runs, end)| (runs, end.record, end.offset));
+
+    assert_eq!(
+        runs.ok(),
+        Some((
+            vec![
+                "0\n".to_owned(),
+                "1\n".to_owned(),
+                "2\n".to_owned(),
+                "3\n".to_owned()
+            ],
+            4,
+            10
+        ))
+    );
+}
+
+#[test]
+fn consecutive_crlf_records() {
+    let runs = runs("0\r\n1\r\n2\n", 64).map(|(runs, end)| (runs, end.offset));
+
+    assert_eq!(
+        runs.ok(),
+        Some((vec!["0\n1\n".to_owned(), "2\n".to_owned()], 8))
+    );
+}
+
+#[test]
+fn bounded_crlf_copy() {
+    let buffer = "0\r\n".repeat(MAX_COPIED);
+    let mut copied = Vec::new();
+
+    let len = copy_crlf(buffer.as_bytes(), &mut copied);
+
+    assert!(len < buffer.len());
+    assert!(copied.len() <= MAX_COPIED);
+    assert_eq!(copied.len(), len / 3 * 2);
+}
+
+#[test]
+fn invalid_utf8_position() {
+    let mut source = io::BufReader::new(&b"0\r\n1\r\n2\xff\r\n"[..]);
+
+    let error = for_each_run(&mut source, Position::default(), |run| {
+        run.take(u64::MAX);
+        Ok(())
+    })
+    .err();
+
+    assert_eq!(
+        error.map(|error| (error.kind(), error.record(), error.offset())),
+        Some((io::ErrorKind::InvalidData, 2, 6))
+    );
+}
+
+#[test]
+fn take_records() {
+    let mut run = Run {
+        records: "0\n1\n2\n",
+        position: Position::default(),
+        ending: LF,
+        fragment: false,
+        continued: false,
+    };
+
+    let taken = run.take(2);
+    let within = run.take_within(1);
+
+    assert_eq!(
+        (taken.as_str(), within.as_str(), run.as_str()),
+        ("0\n1\n", "", "2\n")
+    );
+    assert_eq!(
+        run.position(),
+        Position {
+            record: 2,
+            offset: 4
+        }
+    );
+    assert_eq!(run.take_within(2).as_str(), "2\n");
+    assert!(run.is_empty());
+}
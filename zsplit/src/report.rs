@@ -0,0 +1,150 @@
+//! Statistics of a splitting operation.
+
+use std::io;
+use std::time::Duration;
+
+#[cfg(test)]
+#[path = "./report_test.rs"]
+mod report_test;
+
+/// What a splitting operation has done.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use zsplit::prelude::*;
+///
+/// let data = "Hello\nWorld,\n42!";
+/// let mut source = std::io::BufReader::new(data.as_bytes());
+/// let mut destinations = [Destination::buffer_with_lines(2), Destination::buffer()];
+///
+/// let report = split_round_robin(&mut source, &mut destinations).unwrap();
+///
+/// assert_eq!(report.records_written, 3);
+/// assert_eq!(report.destinations[0].records, 2);
+/// assert_eq!(report.destinations[1].bytes, 4);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SplitReport {
+    /// The number of records written to all destinations.
+    ///
+    /// Records, which were dropped before splitting, aren't part of it.
+    pub records_written: u64,
+
+    /// The statistics of every destination in the same order as the destinations.
+    pub destinations: Vec<DestinationReport>,
+
+    /// The wall time of the splitting operation.
+    pub elapsed: Duration,
+
+    /// The destinations dropped by [`ErrorPolicy::Skip`](crate::policy::ErrorPolicy::Skip).
+    pub failures: Vec<DestinationFailure>,
+}
+
+/// What was written to a single destination.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DestinationReport {
+    /// The number of records written.
+    pub records: u64,
+
+    /// The number of bytes written including the line endings.
+    pub bytes: u64,
+}
+
+/// Why a destination was dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DestinationFailure {
+    /// The index of the destination.
+    pub index: usize,
+
+    /// The number of the record, which couldn't be written, starting at 0.
+    pub record: u64,
+
+    /// The kind of the IO-error.
+    pub kind: io::ErrorKind,
+
+    /// The message of the IO-error.
+    pub message: String,
+}
+
+impl SplitReport {
+    pub(crate) fn new(destinations_len: usize) -> Self {
+        Self {
+            destinations: vec![DestinationReport::default(); destinations_len],
+            ..Self::default()
+        }
+    }
+
+    /// Accounts `records` records of `bytes` bytes written to the destination at `index`.
+    pub(crate) fn records(&mut self, index: usize, records: u64, bytes: usize) {
+        let bytes = u64::try_from(bytes).unwrap_or(u64::MAX);
+
+        if index >= self.destinations.len() {
+            self.destinations
+                .resize(index + 1, DestinationReport::default());
+        }
+
+        self.records_written += records;
+        self.destinations[index].records += records;
+        self.destinations[index].bytes += bytes;
+    }
+
+    /// Drops the destination at `index` because of `error` at the record with the number `record`.
+    pub(crate) fn fail(&mut self, index: usize, record: u64, error: &io::Error) {
+        self.failures.push(DestinationFailure {
+            index,
+            record,
+            kind: error.kind(),
+            message: error.to_string(),
+        });
+    }
+
+    /// Whether the destination at `index` was dropped.
+    #[must_use]
+    pub fn is_failed(&self, index: usize) -> bool {
+        self.failures.iter().any(|failure| failure.index == index)
+    }
+
+    /// The number of bytes written to all destinations.
+    #[must_use]
+    pub fn bytes_written(&self) -> u64 {
+        self.destinations
+            .iter()
+            .map(|destination| destination.bytes)
+            .sum()
+    }
+
+    /// The written bytes per second.
+    ///
+    /// Returns `None` when no time has elapsed.
+    #[must_use]
+    pub fn throughput(&self) -> Option<u64> {
+        let nanos = self.elapsed.as_nanos();
+        if nanos == 0 {
+            return None;
+        }
+
+        u64::try_from(u128::from(self.bytes_written()) * 1_000_000_000 / nanos).ok()
+    }
+
+    /// Adds the statistics of a subsequent splitting operation into the same destinations.
+    pub fn merge(&mut self, other: &Self) {
+        self.records_written += other.records_written;
+        self.elapsed += other.elapsed;
+
+        if self.destinations.len() < other.destinations.len() {
+            self.destinations
+                .resize(other.destinations.len(), DestinationReport::default());
+        }
+        for (destination, other) in self.destinations.iter_mut().zip(&other.destinations) {
+            destination.records += other.records;
+            destination.bytes += other.bytes;
+        }
+        self.failures.extend_from_slice(&other.failures);
+    }
+}
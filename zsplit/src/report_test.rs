@@ -0,0 +1,66 @@
+use super::*;
+
+#[test]
+fn record_counts_destination() {
+    let mut report = SplitReport::new(2);
+    report.records(1, 1, 4);
+    report.records(1, 1, 2);
+
+    assert_eq!(report.records_written, 2);
+    assert_eq!(report.destinations[0], DestinationReport::default());
+    assert_eq!(
+        report.destinations[1],
+        DestinationReport {
+            records: 2,
+            bytes: 6
+        }
+    );
+    assert_eq!(report.bytes_written(), 6);
+}
+
+#[test]
+fn throughput_per_second() {
+    let mut report = SplitReport::new(1);
+    report.records(0, 1, 500);
+    report.elapsed = Duration::from_millis(500);
+
+    assert_eq!(report.throughput(), Some(1000));
+}
+
+#[test]
+fn no_throughput_without_elapsed_time() {
+    assert_eq!(SplitReport::new(1).throughput(), None);
+}
+
+#[test]
+fn merge_reports() {
+    let mut report = SplitReport::new(2);
+    report.records(0, 1, 2);
+    report.elapsed = Duration::from_secs(1);
+
+    let mut other = SplitReport::new(2);
+    other.records(0, 1, 3);
+    other.records(1, 1, 4);
+    other.elapsed = Duration::from_secs(2);
+
+    report.merge(&other);
+
+    assert_eq!(report.records_written, 3);
+    assert_eq!(report.elapsed, Duration::from_secs(3));
+    assert_eq!(report.destinations[0].bytes, 5);
+    assert_eq!(report.destinations[1].records, 1);
+}
+
+#[test]
+fn failure_at_record() {
+    let mut report = SplitReport::new(2);
+    report.records(0, 1, 2);
+    report.fail(1, 5, &io::ErrorKind::BrokenPipe.into());
+
+    let failure = &report.failures[0];
+    assert_eq!(
+        (failure.index, failure.record, failure.kind),
+        (1, 5, io::ErrorKind::BrokenPipe)
+    );
+    assert_eq!((report.is_failed(0), report.is_failed(1)), (false, true));
+}
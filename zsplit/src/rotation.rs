@@ -0,0 +1,173 @@
+//! Rotation of files, which reached a limit.
+
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+#[path = "./rotation_test.rs"]
+mod rotation_test;
+
+/// The limits of a file, before the following records are written to the next file.
+///
+/// The first file has the path of the [`Destination`](crate::Destination), the following ones are
+/// suffixed with `.1`, `.2`, ... .
+/// A file is rotated at the record boundary before it would exceed the limits, so a file only
+/// exceeds `max_bytes` when a single record is larger.
+/// The limits apply to the uncompressed content.
+/// With [`WritePolicy::Append`](crate::file::WritePolicy::Append) the content of the existing
+/// files counts towards the limits, with
+/// [`WritePolicy::Overwrite`](crate::file::WritePolicy::Overwrite) the following files left by an
+/// earlier rotation are removed, when the destination is finished.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```no_run
+/// use zsplit::file::FileOptions;
+/// use zsplit::prelude::*;
+/// use zsplit::rotation::Rotation;
+///
+/// let options = FileOptions::new().rotation(Rotation::new().max_bytes(100_000_000));
+///
+/// Destination::new_with_path_and_options("a.txt", 1, &options).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rotation {
+    max_bytes: Option<u64>,
+    max_lines: Option<u64>,
+}
+
+impl Rotation {
+    /// Creates a [`Rotation`] without limits.
+    #[must_use]
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the bytes of a file.
+    #[must_use]
+    #[inline]
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Limits the lines of a file.
+    #[must_use]
+    #[inline]
+    pub fn max_lines(mut self, max_lines: u64) -> Self {
+        self.max_lines = Some(max_lines);
+        self
+    }
+
+    fn is_exceeded(self, bytes: u64, lines: u64, record_len: u64) -> bool {
+        self.max_bytes.map_or(false, |max| bytes + record_len > max)
+            || self.max_lines.map_or(false, |max| lines >= max)
+    }
+}
+
+/// Tracks the content of the current file of a [`Rotation`].
+#[derive(Debug)]
+pub(crate) struct Rotator {
+    rotation: Rotation,
+    path: PathBuf,
+    index: usize,
+    bytes: u64,
+    lines: u64,
+    at_record_boundary: bool,
+}
+
+impl Rotator {
+    pub(crate) fn new(rotation: Rotation, path: &Path) -> Self {
+        Self {
+            rotation,
+            path: path.to_owned(),
+            index: 0,
+            bytes: 0,
+            lines: 0,
+            at_record_boundary: true,
+        }
+    }
+
+    /// The first record or the remainder of it in `buf`.
+    pub(crate) fn chunk(buf: &[u8]) -> &[u8] {
+        buf.iter()
+            .position(|byte| *byte == b'\n')
+            .map_or(buf, |newline| &buf[..=newline])
+    }
+
+    /// Returns the path of the next file, when the `chunk` has to be written into it.
+    pub(crate) fn rotate(&mut self, chunk: &[u8]) -> Option<PathBuf> {
+        if !self.at_record_boundary || self.bytes == 0 {
+            return None;
+        }
+
+        // The record is at least terminated by a newline.
+        let record_len = to_u64(chunk.len()) + u64::from(!chunk.ends_with(b"\n"));
+        if !self
+            .rotation
+            .is_exceeded(self.bytes, self.lines, record_len)
+        {
+            return None;
+        }
+
+        self.index += 1;
+        self.bytes = 0;
+        self.lines = 0;
+        Some(self.path_of(self.index))
+    }
+
+    /// The paths of the files following the current one, which were left by an earlier rotation.
+    pub(crate) fn stale(&self) -> Vec<PathBuf> {
+        let mut stale = Vec::new();
+        let mut path = self.path_of(self.index + 1);
+
+        while path.exists() {
+            stale.push(path);
+            path = self.path_of(self.index + 1 + stale.len());
+        }
+        stale
+    }
+
+    /// Counts the `content` of the current file, which is appended.
+    pub(crate) fn count(&mut self, mut content: impl BufRead) -> io::Result<()> {
+        loop {
+            let buffer = content.fill_buf()?;
+            if buffer.is_empty() {
+                return Ok(());
+            }
+
+            let len = buffer.len();
+            let lines = memchr::memchr_iter(b'\n', buffer).count();
+            self.at_record_boundary = buffer.ends_with(b"\n");
+            self.lines += to_u64(lines);
+            self.bytes += to_u64(len);
+            content.consume(len);
+        }
+    }
+
+    fn path_of(&self, index: usize) -> PathBuf {
+        if index == 0 {
+            return self.path.clone();
+        }
+
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{index}"));
+        path.into()
+    }
+
+    /// Counts the `written` part of a chunk.
+    pub(crate) fn update(&mut self, written: &[u8]) {
+        if let Some(last) = written.last() {
+            self.at_record_boundary = *last == b'\n';
+            self.lines += u64::from(self.at_record_boundary);
+        }
+        self.bytes += to_u64(written.len());
+    }
+}
+
+fn to_u64(value: usize) -> u64 {
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
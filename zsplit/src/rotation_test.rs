@@ -0,0 +1,78 @@
+use super::*;
+
+fn write(rotator: &mut Rotator, data: &str) -> Vec<Option<PathBuf>> {
+    let mut data = data.as_bytes();
+    let mut rotations = Vec::new();
+
+    while !data.is_empty() {
+        let chunk = Rotator::chunk(data);
+        rotations.push(rotator.rotate(chunk));
+        rotator.update(chunk);
+        data = &data[chunk.len()..];
+    }
+
+    rotations
+}
+
+#[test]
+fn chunk_of_first_record() {
+    assert_eq!(Rotator::chunk(b"ab\ncd\n"), b"ab\n");
+    assert_eq!(Rotator::chunk(b"ab"), b"ab");
+    assert_eq!(Rotator::chunk(b""), b"");
+}
+
+#[test]
+fn rotate_by_lines() {
+    let mut rotator = Rotator::new(Rotation::new().max_lines(2), Path::new("a.txt"));
+
+    assert_eq!(
+        write(&mut rotator, "0\n1\n2\n3\n4\n"),
+        [
+            None,
+            None,
+            Some(PathBuf::from("a.txt.1")),
+            None,
+            Some(PathBuf::from("a.txt.2"))
+        ]
+    );
+}
+
+#[test]
+fn rotate_by_bytes_before_exceeding() {
+    let mut rotator = Rotator::new(Rotation::new().max_bytes(5), Path::new("a"));
+
+    assert_eq!(
+        write(&mut rotator, "0\n1\n22\n"),
+        [None, None, Some(PathBuf::from("a.1"))]
+    );
+}
+
+#[test]
+fn rotate_at_record_boundary() {
+    let mut rotator = Rotator::new(Rotation::new().max_bytes(3), Path::new("a"));
+
+    assert_eq!(write(&mut rotator, "0\n"), [None]);
+    assert_eq!(write(&mut rotator, "1"), [Some(PathBuf::from("a.1"))]);
+    assert_eq!(write(&mut rotator, "1111\n"), [None]);
+    assert_eq!(write(&mut rotator, "2\n"), [Some(PathBuf::from("a.2"))]);
+}
+
+#[test]
+fn no_rotation_without_limits() {
+    let mut rotator = Rotator::new(Rotation::new(), Path::new("a"));
+
+    assert_eq!(write(&mut rotator, "0\n1\n"), [None, None]);
+}
+
+#[test]
+fn count_appended_content() -> io::Result<()> {
+    let mut rotator = Rotator::new(Rotation::new().max_lines(3), Path::new("a"));
+
+    rotator.count("0\n1\n".as_bytes())?;
+
+    assert_eq!(
+        write(&mut rotator, "2\n3\n"),
+        [None, Some(PathBuf::from("a.1"))]
+    );
+    Ok(())
+}
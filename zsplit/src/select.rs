@@ -0,0 +1,212 @@
+//! Selecting a range of the records of a source.
+
+use std::fmt;
+use std::io::{self, BufRead, Read};
+use std::str::FromStr;
+
+#[cfg(test)]
+#[path = "./select_test.rs"]
+mod select_test;
+
+/// A range of records numbered from 0, which excludes its end.
+///
+/// It can be parsed from `START..END`, where both bounds could be omitted like `1000..` or
+/// `..5000`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use zsplit::select::RecordRange;
+///
+/// assert_eq!("1000..5000".parse(), Ok(RecordRange::new(1000, Some(5000))));
+/// assert_eq!("..5000".parse(), Ok(RecordRange::new(0, Some(5000))));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RecordRange {
+    /// The number of the first selected record.
+    pub start: u64,
+
+    /// The number of the first record after the range, which is unbounded without it.
+    pub end: Option<u64>,
+}
+
+impl RecordRange {
+    /// Creates a [`RecordRange`].
+    ///
+    /// An `end` before `start` selects no record.
+    #[must_use]
+    #[inline]
+    pub fn new(start: u64, end: Option<u64>) -> Self {
+        Self { start, end }
+    }
+
+    /// The count of selected records, which is unbounded without an end.
+    fn len(self) -> Option<u64> {
+        self.end.map(|end| end.saturating_sub(self.start))
+    }
+}
+
+/// The error of parsing an invalid [`RecordRange`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidRecordRange(String);
+
+impl fmt::Display for InvalidRecordRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid record range `{}`, expected `START..END` like `1000..5000`, `1000..` or `..5000`",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for InvalidRecordRange {}
+
+impl FromStr for RecordRange {
+    type Err = InvalidRecordRange;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let parsed = range.split_once("..").and_then(|(start, end)| {
+            let (start, end) = (bound(start).ok()?.unwrap_or(0), bound(end).ok()?);
+
+            match end {
+                Some(end) if end < start => None,
+                _ => Some(Self::new(start, end)),
+            }
+        });
+
+        parsed.ok_or_else(|| InvalidRecordRange(range.to_owned()))
+    }
+}
+
+/// Parses an optional bound of a [`RecordRange`].
+fn bound(bound: &str) -> Result<Option<u64>, std::num::ParseIntError> {
+    match bound {
+        "" => Ok(None),
+        bound => bound.parse().map(Some),
+    }
+}
+
+/// A source, which contains only the records of a [`RecordRange`].
+///
+/// The records before the range are skipped without copying them and the source isn't read after
+/// the range.
+/// The positions of errors during splitting refer to the selected records.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use zsplit::prelude::*;
+/// use zsplit::select::{RecordRange, SelectedRecords};
+///
+/// let mut source = SelectedRecords::new("0\n1\n2\n3\n".as_bytes(), RecordRange::new(1, Some(3)));
+/// let mut destinations = [Destination::buffer()];
+///
+/// split_round_robin(&mut source, &mut destinations).unwrap();
+///
+/// let [destination] = destinations;
+/// assert_eq!(destination.into_utf8_string().unwrap(), "1\n2\n");
+/// ```
+#[derive(Debug)]
+pub struct SelectedRecords<R> {
+    source: R,
+    /// The count of records, which still have to be skipped.
+    skip: u64,
+    /// The count of records, which can still be read.
+    remaining: Option<u64>,
+}
+
+impl<R: BufRead> SelectedRecords<R> {
+    /// Selects the records of `source` in `range`.
+    #[inline]
+    pub fn new(source: R, range: RecordRange) -> Self {
+        Self {
+            source,
+            skip: range.start,
+            remaining: range.len(),
+        }
+    }
+
+    /// Consumes the [`SelectedRecords`], returning the source.
+    #[must_use]
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+
+    /// Consumes the records before the range.
+    fn skip_records(&mut self) -> io::Result<()> {
+        while self.skip > 0 {
+            let buffer = self.source.fill_buf()?;
+            if buffer.is_empty() {
+                break;
+            }
+
+            let (len, records) = records_len(buffer, self.skip);
+            self.source.consume(len);
+            self.skip -= records;
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for SelectedRecords<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: BufRead> BufRead for SelectedRecords<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.skip_records()?;
+
+        match self.remaining {
+            Some(0) => Ok(&[]),
+            Some(remaining) => {
+                let buffer = self.source.fill_buf()?;
+                Ok(&buffer[..records_len(buffer, remaining).0])
+            }
+            None => self.source.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if let (Some(remaining), true) = (&mut self.remaining, amt > 0) {
+            if let Ok(buffer) = self.source.fill_buf() {
+                let consumed = buffer.get(..amt).unwrap_or(buffer);
+                let records = memchr::memchr_iter(b'\n', consumed).count();
+                *remaining -= u64::try_from(records).unwrap_or(u64::MAX).min(*remaining);
+            }
+        }
+        self.source.consume(amt);
+    }
+}
+
+/// The length of the start of the `buffer` with at most `max` complete records and their count.
+///
+/// The start contains the whole `buffer`, if it has less records.
+fn records_len(buffer: &[u8], max: u64) -> (usize, u64) {
+    let max = usize::try_from(max).unwrap_or(usize::MAX);
+
+    match memchr::memchr_iter(b'\n', buffer)
+        .take(max)
+        .enumerate()
+        .last()
+    {
+        Some((index, end)) if index + 1 == max => (end + 1, u64::try_from(max).unwrap_or(u64::MAX)),
+        records => (
+            buffer.len(),
+            records.map_or(0, |(index, _)| u64::try_from(index + 1).unwrap_or(u64::MAX)),
+        ),
+    }
+}
@@ -0,0 +1,59 @@
+use super::*;
+
+/// Reads the records of the `source` in `range` with a buffer of `capacity` bytes.
+fn read(source: &str, capacity: usize, range: RecordRange) -> io::Result<String> {
+    let source = io::BufReader::with_capacity(capacity, source.as_bytes());
+    let mut read = String::new();
+
+    SelectedRecords::new(source, range).read_to_string(&mut read)?;
+    Ok(read)
+}
+
+#[test]
+fn parse_ranges() {
+    assert_eq!("2..5".parse(), Ok(RecordRange::new(2, Some(5))));
+    assert_eq!("2..".parse(), Ok(RecordRange::new(2, None)));
+    assert_eq!("..5".parse(), Ok(RecordRange::new(0, Some(5))));
+    assert_eq!("..".parse(), Ok(RecordRange::default()));
+}
+
+#[test]
+fn parse_invalid_ranges() {
+    for range in ["", "5", "5..2", "a..", "-1..2", "1...2"] {
+        assert_eq!(
+            range.parse::<RecordRange>(),
+            Err(InvalidRecordRange(range.to_owned()))
+        );
+    }
+}
+
+#[test]
+fn select_range() {
+    let read = read("0\n1\n2\n3\n4\n", 3, RecordRange::new(1, Some(3)));
+
+    assert_eq!(read.ok(), Some("1\n2\n".to_owned()));
+}
+
+#[test]
+fn skip_records() {
+    let read = read("0\n11\n2\n3", 2, RecordRange::new(2, None));
+
+    assert_eq!(read.ok(), Some("2\n3".to_owned()));
+}
+
+#[test]
+fn range_beyond_source() {
+    let read = read("0\n1\n2", 64, RecordRange::new(1, Some(10)));
+
+    assert_eq!(read.ok(), Some("1\n2".to_owned()));
+    assert_eq!(
+        self::read("0\n1\n", 64, RecordRange::new(5, None)).ok(),
+        Some(String::new())
+    );
+}
+
+#[test]
+fn len_of_records() {
+    assert_eq!(records_len(b"0\n1\n2", 2), (4, 2));
+    assert_eq!(records_len(b"0\n1", 2), (3, 1));
+}
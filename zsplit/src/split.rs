@@ -1,18 +1,36 @@
 //! Algorithms for splitting a source into destination sinks.
 
+use crate::checkpoint::Checkpoint;
+use crate::error::{Error, Position};
+use crate::flush::Unflushed;
+use crate::keyed::KeyedDestinations;
+use crate::policy::ErrorPolicy;
+use crate::records::{for_each_run, Run};
+use crate::report::SplitReport;
+use crate::transform::Written;
 use crate::Destination;
-use io::{BufRead, Write};
+use io::{BufRead, Seek, SeekFrom, Write};
 use std::io;
+use std::num::NonZeroU64;
+use std::time::Instant;
 
 #[cfg(test)]
 #[path = "./split_test.rs"]
 mod split_test;
 
+/// A checkpoint interval, which is never reached.
+const NEVER: NonZeroU64 = match NonZeroU64::new(u64::MAX) {
+    Some(never) => never,
+    None => unreachable!(),
+};
+
 /// Splits the `source` round robin like into `destinations`.
 ///
+/// Returns a [`SplitReport`] with the records and bytes written to every destination.
+///
 /// # Errors
 ///
-/// Shortcircuits with the IO-error when there is an error during reading
+/// Shortcircuits with an [`Error`] when there is an error during reading
 /// from the `source` or writing to one of the `destinations`.
 ///
 /// # Examples
@@ -44,7 +62,8 @@ mod split_test;
 ///     Destination::buffer(), // second_destination
 /// ];
 ///
-/// split_round_robin(&mut source, &mut destinations).unwrap();
+/// let report = split_round_robin(&mut source, &mut destinations).unwrap();
+/// assert_eq!(report.records_written, 3);
 ///
 /// let second_destination = destinations.pop().unwrap();
 /// let first_destination = destinations.pop().unwrap();
@@ -55,21 +74,379 @@ mod split_test;
 pub fn round_robin<S: Write>(
     source: &mut dyn BufRead,
     destinations: &mut [Destination<S>],
-) -> io::Result<()> {
-    let mapped_line_destinations = round_robin::map_line_destinations(destinations);
+) -> Result<SplitReport, Error> {
+    round_robin_with_policy(source, destinations, ErrorPolicy::FailFast)
+}
+
+/// Splits the `source` round robin like into `destinations` and handles failing writes by
+/// `policy`.
+///
+/// Returns a [`SplitReport`] with the records and bytes written to every destination and the
+/// dropped destinations.
+///
+/// # Errors
+///
+/// Shortcircuits with an [`Error`] when there is an error during reading from the `source` or
+/// `policy` gives up writing to one of the `destinations`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use zsplit::policy::ErrorPolicy;
+/// use zsplit::prelude::*;
+///
+/// let mut source = "Hello\nWorld,\n42!".as_bytes();
+/// let mut destinations = [Destination::buffer(), Destination::buffer()];
+///
+/// let report =
+///     split_round_robin_with_policy(&mut source, &mut destinations, ErrorPolicy::Retry(3))
+///         .unwrap();
+///
+/// assert!(report.failures.is_empty());
+/// ```
+pub fn round_robin_with_policy<S: Write>(
+    source: &mut dyn BufRead,
+    destinations: &mut [Destination<S>],
+    policy: ErrorPolicy,
+) -> Result<SplitReport, Error> {
+    round_robin_resumable(
+        source,
+        destinations,
+        policy,
+        &Checkpoint::default(),
+        NEVER,
+        |_| Ok(()),
+    )
+}
+
+/// Splits the `source` round robin like into `destinations` starting at the checkpoint `resume`
+/// and calls `save` with a [`Checkpoint`] after `every` records and at the end.
+///
+/// The `source` has to be positioned at the offset of `resume` and the destinations have to
+/// contain exactly its bytes.
+/// The distribution continues with the destination of the record following `resume`.
+/// The destinations are flushed before every checkpoint.
+/// A destination dropped by [`ErrorPolicy::Skip`] isn't part of the checkpoint.
+///
+/// # Errors
+///
+/// Shortcircuits with an [`Error`] when there is an error during reading from the `source`,
+/// `policy` gives up writing to one of the `destinations` or `save` fails.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use std::num::NonZeroU64;
+/// use zsplit::checkpoint::Checkpoint;
+/// use zsplit::policy::ErrorPolicy;
+/// use zsplit::prelude::*;
+///
+/// let mut source = "2\n3\n".as_bytes();
+/// let mut destinations = [Destination::buffer(), Destination::buffer()];
+/// let resume = Checkpoint::new(1, 2, vec![2, 0]);
+/// let mut checkpoints = Vec::new();
+///
+/// split_round_robin_resumable(
+///     &mut source,
+///     &mut destinations,
+///     ErrorPolicy::FailFast,
+///     &resume,
+///     NonZeroU64::new(1).unwrap(),
+///     |checkpoint| {
+///         checkpoints.push(checkpoint.clone());
+///         Ok(())
+///     },
+/// )
+/// .unwrap();
+///
+/// assert_eq!(checkpoints[0], Checkpoint::new(2, 4, vec![2, 2]));
+/// assert_eq!(checkpoints.last(), Some(&Checkpoint::new(3, 6, vec![4, 2])));
+/// ```
+pub fn round_robin_resumable<S, F>(
+    source: &mut dyn BufRead,
+    destinations: &mut [Destination<S>],
+    policy: ErrorPolicy,
+    resume: &Checkpoint,
+    every: NonZeroU64,
+    mut save: F,
+) -> Result<SplitReport, Error>
+where
+    S: Write,
+    F: FnMut(&Checkpoint) -> io::Result<()>,
+{
+    let start = Instant::now();
+    let mut splitter = round_robin::Splitter::new(destinations, policy, resume.record);
+
+    let mut checkpoint = |splitter: &mut round_robin::Splitter<'_, S>, end: Position| {
+        splitter.flush_healthy(end)?;
+        save(&splitter.checkpoint(resume, end)).map_err(|error| Error::checkpoint(end, error))
+    };
 
-    round_robin::write_lines(source, destinations, &mapped_line_destinations)?;
+    let end = for_each_run(source, resume.position(), |run| {
+        while !run.is_empty() {
+            let until_checkpoint = every.get() - run.position().record % every;
+            let part = run.take(until_checkpoint);
+            splitter.write_run(part)?;
 
-    flush_buffers(destinations)?;
+            if !part.is_fragment() && run.position().record % every == 0 {
+                checkpoint(&mut splitter, run.position())?;
+            }
+        }
+        Ok(())
+    })?;
 
+    checkpoint(&mut splitter, end)?;
+
+    let mut report = splitter.into_report();
+    report.elapsed = start.elapsed();
+    Ok(report)
+}
+
+/// Splits the `source` into the destinations of the keys of the records.
+///
+/// `key_of` extracts the key of a record, for which `create` creates a sink on its first
+/// appearance.
+/// The key of a record exceeding the buffer of the `source` is extracted from its first fragment.
+/// In contrast to [`round_robin`] the destinations are created during the splitting.
+///
+/// # Errors
+///
+/// Shortcircuits with an [`Error`] when there is an error during reading from the `source`,
+/// creating a sink or writing to one of the `destinations`.
+/// Exceeding the maximal count of keys of the `destinations` is an error as well.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use zsplit::keyed::KeyedDestinations;
+/// use zsplit::prelude::*;
+///
+/// let mut source = "alice 1\nbob 2\nalice 3\n".as_bytes();
+/// let mut destinations = KeyedDestinations::new();
+///
+/// let report = split_by_key(
+///     &mut source,
+///     &mut destinations,
+///     |line| line.split(' ').next().unwrap_or_default(),
+///     |_key| Ok(std::io::sink()),
+/// )
+/// .unwrap();
+///
+/// assert_eq!(destinations.len(), 2);
+/// assert_eq!(report.destinations[0].records, 2);
+/// ```
+pub fn by_key<S, K, C>(
+    source: &mut dyn BufRead,
+    destinations: &mut KeyedDestinations<S>,
+    mut key_of: K,
+    mut create: C,
+) -> Result<SplitReport, Error>
+where
+    S: Write,
+    K: FnMut(&str) -> &str,
+    C: FnMut(&str) -> io::Result<S>,
+{
+    let start = Instant::now();
+    let mut report = SplitReport::new(destinations.len());
+    let mut unflushed = Unflushed::default();
+    let mut streamed = None;
+
+    let end = for_each_run(source, Position::default(), |run| {
+        while !run.is_empty() {
+            let record = run.take(1);
+            let index = match streamed {
+                Some(index) if record.is_continued() => index,
+                _ => destination_of(destinations, &mut key_of, &mut create, &record)?,
+            };
+            streamed = record.is_fragment().then_some(index);
+
+            let position = record.position();
+            write_keyed(
+                destinations.destination_mut(index),
+                index,
+                record,
+                &mut report,
+                &mut unflushed,
+            )?;
+            flush_overdue(destinations, &mut unflushed, position)?;
+        }
+        Ok(())
+    })?;
+
+    destinations
+        .flush()
+        .map_err(|(index, error)| Error::write(end, Some(index), error))?;
+
+    report.elapsed = start.elapsed();
+    Ok(report)
+}
+
+/// The index of the destination of the key of the `record`, which is created on its first
+/// appearance.
+fn destination_of<S, K, C>(
+    destinations: &mut KeyedDestinations<S>,
+    key_of: &mut K,
+    create: &mut C,
+    record: &Run<'_>,
+) -> Result<usize, Error>
+where
+    S: Write,
+    K: FnMut(&str) -> &str,
+    C: FnMut(&str) -> io::Result<S>,
+{
+    let line = record.as_str();
+    let key = key_of(line.strip_suffix('\n').unwrap_or(line));
+
+    destinations
+        .index(key, create)
+        .map_err(|error| Error::create(record.position(), destinations.len(), error))
+}
+
+/// Writes the `record` or fragment of a record to the `destination` at `index`.
+fn write_keyed<S: Write>(
+    destination: &mut Destination<S>,
+    index: usize,
+    record: Run<'_>,
+    report: &mut SplitReport,
+    unflushed: &mut Unflushed,
+) -> Result<(), Error> {
+    let position = record.position();
+    let records = u64::from(!record.is_fragment());
+    let mut written = Written::default();
+
+    destination
+        .write_run(&record, &mut written)
+        .map_err(|error| Error::write(position, Some(index), error))?;
+    report.records(index, records, written.sink);
+
+    if unflushed.record(index, records, destination.flush_policy) {
+        destination
+            .flush()
+            .map_err(|error| Error::write(position, Some(index), error))?;
+    }
     Ok(())
 }
 
+/// Flushes the `destinations`, whose interval elapsed since their last flush.
+fn flush_overdue<S: Write>(
+    destinations: &mut KeyedDestinations<S>,
+    unflushed: &mut Unflushed,
+    position: Position,
+) -> Result<(), Error> {
+    unflushed.overdue().into_iter().try_for_each(|index| {
+        destinations
+            .destination_mut(index)
+            .flush()
+            .map_err(|error| Error::write(position, Some(index), error))
+    })
+}
+
+/// Splits the seekable source opened by `open` into contiguous chunks, which are written to their
+/// destinations in parallel.
+///
+/// The sizes of the chunks are proportional to the `assigned_lines` of the destinations.
+/// Their boundaries are moved to the start of the following record, so every record stays in one
+/// piece.
+/// Every destination is written by a separate thread, which opens the source on its own and reads
+/// only its chunk.
+/// The destinations are flushed by their [`FlushPolicy`](crate::flush::FlushPolicy) and at the
+/// end of their chunk.
+///
+/// # Errors
+///
+/// Returns the [`Error`] of the first destination, whose thread failed opening or reading the
+/// source or writing its chunk.
+/// The records of the error are counted from the start of the chunk.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// use std::io::Cursor;
+/// use zsplit::prelude::*;
+///
+/// let data = "Hello\nWorld,\n42!";
+/// let mut destinations = [Destination::buffer(), Destination::buffer()];
+///
+/// let report = split_contiguous(|| Ok(Cursor::new(data)), &mut destinations).unwrap();
+/// assert_eq!(report.records_written, 3);
+///
+/// let [first, second] = destinations;
+/// assert_eq!(first.into_utf8_string().unwrap(), "Hello\nWorld,\n");
+/// assert_eq!(second.into_utf8_string().unwrap(), "42!\n");
+/// ```
+pub fn contiguous<R, O, S>(
+    open: O,
+    destinations: &mut [Destination<S>],
+) -> Result<SplitReport, Error>
+where
+    R: BufRead + Seek,
+    O: Fn() -> io::Result<R> + Sync,
+    S: Write + Send,
+{
+    let start = Instant::now();
+    let len = open()
+        .and_then(|mut source| source.seek(SeekFrom::End(0)))
+        .map_err(|error| Error::read(Position::default(), error))?;
+    let bounds = contiguous::bounds(destinations, len);
+
+    let chunks = std::thread::scope(|scope| {
+        let open = &open;
+        let threads = destinations
+            .iter_mut()
+            .zip(bounds.windows(2))
+            .enumerate()
+            .map(|(index, (destination, bounds))| {
+                scope.spawn(move || {
+                    contiguous::write_chunk(open, destination, index, (bounds[0], bounds[1]))
+                })
+            })
+            .collect::<Vec<_>>();
+
+        threads
+            .into_iter()
+            .map(|thread| {
+                thread
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let mut report = SplitReport::new(destinations.len());
+    for chunk in chunks {
+        report.merge(&chunk?);
+    }
+    report.elapsed = start.elapsed();
+    Ok(report)
+}
+
 /// Round Robin specific algorithms.
 mod round_robin {
+    use crate::checkpoint::Checkpoint;
+    use crate::error::{Error, Position};
+    use crate::flush::Unflushed;
+    use crate::policy::ErrorPolicy;
+    use crate::records::Run;
+    use crate::report::SplitReport;
+    use crate::transform::Written;
     use crate::Destination;
-    use io::{BufRead, Write};
+    use io::Write;
     use std::io;
+    use std::time::Duration;
+
+    /// The delay before the first retry of a failed write.
+    const RETRY_DELAY: Duration = Duration::from_millis(10);
+
+    /// The limit of the doubling delay between retries.
+    const MAX_RETRY_DELAY: Duration = Duration::from_secs(1);
 
     /// Maps a [`Destination`] with the line number.
     ///
@@ -88,28 +465,405 @@ mod round_robin {
             .collect()
     }
 
-    pub(crate) fn write_lines<S: Write>(
-        source: &mut dyn BufRead,
-        destinations: &mut [Destination<S>],
-        mapped_line_destinations: &[usize],
-    ) -> io::Result<()> {
-        let line_ring_size = mapped_line_destinations.len();
+    /// The cycle through the mapped line destinations, which are not dropped.
+    #[derive(Debug)]
+    pub(crate) struct Ring {
+        slots: Vec<usize>,
+        /// The count of the consecutive slots of the same destination starting at every slot.
+        runs: Vec<u64>,
+        position: usize,
+    }
 
-        source
-            .lines()
-            .enumerate()
-            .try_for_each(|(line_index, line)| {
-                let line_index = line_index % line_ring_size;
+    impl Ring {
+        pub(crate) fn new<S: Write>(destinations: &[Destination<S>]) -> Self {
+            let slots = map_line_destinations(destinations);
 
-                let sink = &mut destinations[mapped_line_destinations[line_index]];
+            Self {
+                runs: runs(&slots),
+                slots,
+                position: 0,
+            }
+        }
 
-                sink.write_all(line?.as_bytes())?;
-                sink.write_all(b"\n")?;
-                Ok(())
-            })
+        /// The index of the destination of the next record.
+        pub(crate) fn current(&self) -> Option<usize> {
+            self.slots.get(self.position).copied()
+        }
+
+        /// The count of the following records, which belong to the current destination.
+        pub(crate) fn run(&self) -> u64 {
+            self.runs.get(self.position).copied().unwrap_or_default()
+        }
+
+        /// Moves forward by `records` records of the current run.
+        pub(crate) fn forward(&mut self, records: u64) {
+            let records = usize::try_from(records).unwrap_or(usize::MAX);
+            self.position = (self.position + records) % self.slots.len();
+        }
+
+        /// Skips the destinations of `records` records.
+        pub(crate) fn advance(&mut self, records: u64) {
+            let len = u64::try_from(self.slots.len()).unwrap_or(u64::MAX);
+            self.position = records
+                .checked_rem(len)
+                .and_then(|position| usize::try_from(position).ok())
+                .unwrap_or_default();
+        }
+
+        /// Drops the destination at `index`, keeping the position of the following ones.
+        pub(crate) fn remove(&mut self, index: usize) {
+            let preceding = self.slots[..self.position]
+                .iter()
+                .filter(|slot| **slot == index)
+                .count();
+
+            self.slots.retain(|slot| *slot != index);
+            self.runs = runs(&self.slots);
+            self.position = (self.position - preceding)
+                .checked_rem(self.slots.len())
+                .unwrap_or_default();
+        }
+
+        pub(crate) fn is_empty(&self) -> bool {
+            self.slots.is_empty()
+        }
+    }
+
+    /// The count of the consecutive equal slots starting at every slot.
+    fn runs(slots: &[usize]) -> Vec<u64> {
+        let mut runs = vec![1; slots.len()];
+
+        for index in (1..slots.len()).rev() {
+            if slots[index - 1] == slots[index] {
+                runs[index - 1] += runs[index];
+            }
+        }
+        runs
+    }
+
+    /// The destinations of a round robin split with the state of the distribution.
+    pub(crate) struct Splitter<'d, S: Write> {
+        destinations: &'d mut [Destination<S>],
+        ring: Ring,
+        policy: ErrorPolicy,
+        unflushed: Unflushed,
+        report: SplitReport,
+    }
+
+    impl<'d, S: Write> Splitter<'d, S> {
+        /// Creates a [`Splitter`], which continues the distribution after `records` records.
+        pub(crate) fn new(
+            destinations: &'d mut [Destination<S>],
+            policy: ErrorPolicy,
+            records: u64,
+        ) -> Self {
+            let mut ring = Ring::new(destinations);
+            ring.advance(records);
+
+            Self {
+                report: SplitReport::new(destinations.len()),
+                destinations,
+                ring,
+                policy,
+                unflushed: Unflushed::default(),
+            }
+        }
+
+        pub(crate) fn into_report(self) -> SplitReport {
+            self.report
+        }
+
+        /// Writes the records of the `run` as slices to their destinations, which don't fail.
+        pub(crate) fn write_run(&mut self, mut run: Run<'_>) -> Result<(), Error> {
+            while !run.is_empty() {
+                let index = self
+                    .ring
+                    .current()
+                    .ok_or_else(|| Error::write(run.position(), None, no_destination()))?;
+                let mut rest = run;
+                let part = rest.take(self.ring.run());
+                let mut written = Written::default();
+
+                match self.write_part(index, &part, &mut written) {
+                    Ok(()) => {
+                        run = rest;
+                        self.written(index, &part, rest.position(), written.sink)?;
+                    }
+                    // The fragments of a record can't be redistributed.
+                    Err(error) if part.is_continued() => {
+                        return Err(Error::write(part.position(), Some(index), error));
+                    }
+                    Err(error) => self.fail(index, &mut run, written, error)?,
+                }
+            }
+            Ok(())
+        }
+
+        /// Writes the `part` to the destination at `index`.
+        ///
+        /// Under [`ErrorPolicy::Skip`] the destination is flushed as well, so a failure of its
+        /// buffer shows up, while the `part` could still be redistributed.
+        fn write_part(
+            &mut self,
+            index: usize,
+            part: &Run<'_>,
+            written: &mut Written,
+        ) -> io::Result<()> {
+            let destination = &mut self.destinations[index];
+            retrying(self.policy, || destination.write_run(part, written))?;
+
+            if self.policy == ErrorPolicy::Skip {
+                destination.flush().map_err(|error| {
+                    // It's unknown, which records of the buffer reached the sink.
+                    *written = Written::default();
+                    error
+                })?;
+            }
+            Ok(())
+        }
+
+        /// Accounts the `part` ending at `end`, which was written as `bytes` bytes, and flushes its
+        /// destination, if its policy requires it.
+        fn written(
+            &mut self,
+            index: usize,
+            part: &Run<'_>,
+            end: Position,
+            bytes: usize,
+        ) -> Result<(), Error> {
+            let records = end.record - part.position().record;
+            self.account(index, records, bytes);
+
+            if self
+                .unflushed
+                .record(index, records, self.destinations[index].flush_policy)
+            {
+                self.flush(index, part.position())?;
+            }
+
+            for overdue in self.unflushed.overdue() {
+                if !self.report.is_failed(overdue) {
+                    self.flush(overdue, part.position())?;
+                }
+            }
+            Ok(())
+        }
+
+        fn account(&mut self, index: usize, records: u64, bytes: usize) {
+            self.ring.forward(records);
+            self.report.records(index, records, bytes);
+        }
+
+        /// Drops the destination at `index`, which failed after `written` bytes of the `run`.
+        ///
+        /// The completely written records stay in the destination, the others are redistributed.
+        fn fail(
+            &mut self,
+            index: usize,
+            run: &mut Run<'_>,
+            written: Written,
+            error: io::Error,
+        ) -> Result<(), Error> {
+            let start = run.position();
+            run.take_within(written.source);
+            self.account(index, run.position().record - start.record, written.sink);
+
+            self.drop_destination(index, run.position(), error)
+                .map_err(|error| Error::write(run.position(), Some(index), error))
+        }
+
+        /// Flushes the destinations, which are not dropped.
+        pub(crate) fn flush_healthy(&mut self, end: Position) -> Result<(), Error> {
+            for index in 0..self.destinations.len() {
+                if !self.report.is_failed(index) {
+                    self.flush(index, end)?;
+                }
+            }
+            Ok(())
+        }
+
+        /// Flushes the destination at `index` and drops it, when the `policy` allows it.
+        fn flush(&mut self, index: usize, position: Position) -> Result<(), Error> {
+            let destination = &mut self.destinations[index];
+
+            if let Err(error) = retrying(self.policy, || destination.flush()) {
+                self.drop_destination(index, position, error)
+                    .map_err(|error| Error::write(position, Some(index), error))?;
+            }
+            Ok(())
+        }
+
+        /// Drops the destination at `index`, which failed at `position`, if the `policy` allows it
+        /// and it's not the last one.
+        fn drop_destination(
+            &mut self,
+            index: usize,
+            position: Position,
+            error: io::Error,
+        ) -> io::Result<()> {
+            if self.policy != ErrorPolicy::Skip {
+                return Err(error);
+            }
+
+            self.report.fail(index, position.record, &error);
+            self.ring.remove(index);
+
+            if self.ring.is_empty() {
+                return Err(error);
+            }
+            Ok(())
+        }
+
+        /// The checkpoint at `end` of a split resumed at `resume`.
+        pub(crate) fn checkpoint(&self, resume: &Checkpoint, end: Position) -> Checkpoint {
+            let destinations = self
+                .report
+                .destinations
+                .iter()
+                .enumerate()
+                .map(|(index, destination)| resume.bytes(index) + destination.bytes)
+                .collect();
+
+            Checkpoint::new(end.record, end.offset, destinations)
+        }
+    }
+
+    /// Calls `operation` until it succeeds or the retries of the `policy` are exhausted.
+    ///
+    /// The delay before a retry starts at [`RETRY_DELAY`] and doubles with every further one.
+    fn retrying(
+        policy: ErrorPolicy,
+        mut operation: impl FnMut() -> io::Result<()>,
+    ) -> io::Result<()> {
+        let mut retries = policy.retries();
+        let mut delay = RETRY_DELAY;
+        loop {
+            match operation() {
+                Err(_) if retries > 0 => {
+                    retries -= 1;
+                    std::thread::sleep(delay);
+                    delay = (delay * 2).min(MAX_RETRY_DELAY);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn no_destination() -> io::Error {
+        io::Error::new(io::ErrorKind::Other, "There is no destination left")
     }
 }
 
-fn flush_buffers<S: Write>(destinations: &mut [Destination<S>]) -> io::Result<()> {
-    destinations.iter_mut().try_for_each(Destination::flush)
+/// Contiguous chunk specific algorithms.
+mod contiguous {
+    use crate::error::{Error, Position};
+    use crate::flush::Unflushed;
+    use crate::records::for_each_run;
+    use crate::report::SplitReport;
+    use crate::transform::Written;
+    use crate::Destination;
+    use io::{BufRead, Seek, SeekFrom, Write};
+    use std::io;
+
+    /// The byte offsets of the starts of the chunks of the `destinations` in a source of `len`
+    /// bytes followed by `len`, before they are moved to the start of a record.
+    pub(crate) fn bounds<S: Write>(destinations: &[Destination<S>], len: u64) -> Vec<u64> {
+        let total = weight(
+            destinations
+                .iter()
+                .map(|destination| destination.assigned_lines)
+                .sum(),
+        );
+
+        let mut assigned = 0;
+        let mut bounds = vec![0];
+        for destination in destinations {
+            assigned += destination.assigned_lines;
+            let bound = u128::from(len) * weight(assigned) / total.max(1);
+            bounds.push(u64::try_from(bound).unwrap_or(len));
+        }
+        bounds
+    }
+
+    fn weight(assigned_lines: usize) -> u128 {
+        u128::try_from(assigned_lines).unwrap_or(u128::MAX)
+    }
+
+    /// Writes the records of the source opened by `open` between the `bounds` to the
+    /// `destination` at `index`.
+    pub(crate) fn write_chunk<R, O, S>(
+        open: &O,
+        destination: &mut Destination<S>,
+        index: usize,
+        bounds: (u64, u64),
+    ) -> Result<SplitReport, Error>
+    where
+        R: BufRead + Seek,
+        O: Fn() -> io::Result<R>,
+        S: Write,
+    {
+        let read_error = |offset| move |error| Error::read(Position { record: 0, offset }, error);
+        let mut source = open().map_err(read_error(bounds.0))?;
+        let end = record_start(&mut source, bounds.1).map_err(read_error(bounds.1))?;
+        let start = record_start(&mut source, bounds.0).map_err(read_error(bounds.0))?;
+
+        let mut report = SplitReport::new(index + 1);
+        let mut unflushed = Unflushed::default();
+        let mut chunk = source.take(end - start);
+        let start = Position {
+            record: 0,
+            offset: start,
+        };
+
+        let end = for_each_run(&mut chunk, start, |run| {
+            let position = run.position();
+            let records = run.take(u64::MAX);
+            let count = run.position().record - position.record;
+            let mut written = Written::default();
+
+            destination
+                .write_run(&records, &mut written)
+                .map_err(|error| Error::write(position, Some(index), error))?;
+            report.records(index, count, written.sink);
+
+            if unflushed.record(index, count, destination.flush_policy) {
+                destination
+                    .flush()
+                    .map_err(|error| Error::write(position, Some(index), error))?;
+            }
+            Ok(())
+        })?;
+
+        destination
+            .flush()
+            .map_err(|error| Error::write(end, Some(index), error))?;
+        Ok(report)
+    }
+
+    /// Positions the `source` at the start of the first record starting at or after `offset` and
+    /// returns its offset.
+    pub(crate) fn record_start<R: BufRead + Seek>(source: &mut R, offset: u64) -> io::Result<u64> {
+        let previous = match offset.checked_sub(1) {
+            Some(previous) => previous,
+            None => return source.seek(SeekFrom::Start(0)),
+        };
+        source.seek(SeekFrom::Start(previous))?;
+
+        let mut skipped = 0;
+        loop {
+            let buffer = source.fill_buf()?;
+            if buffer.is_empty() {
+                break;
+            }
+
+            let (len, found) =
+                memchr::memchr(b'\n', buffer).map_or((buffer.len(), false), |end| (end + 1, true));
+            source.consume(len);
+            skipped += u64::try_from(len).unwrap_or(u64::MAX);
+            if found {
+                break;
+            }
+        }
+        Ok(previous + skipped)
+    }
 }
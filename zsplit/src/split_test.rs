@@ -1,4 +1,5 @@
 use super::*;
+use crate::policy::ErrorPolicy;
 mod round_robin {
     use super::*;
     use crate::split::round_robin::*;
@@ -39,4 +40,525 @@ mod round_robin {
 
         assert_eq!(mapped_line_destinations[0], 1);
     }
+
+    #[test]
+    fn report_of_split() {
+        let mut source = "0\n1\n2\n30\n".as_bytes();
+        let mut destinations = [Destination::buffer_with_lines(2), Destination::buffer()];
+
+        let report = round_robin(&mut source, &mut destinations);
+
+        let report = report.as_ref().map(|report| {
+            (
+                report.records_written,
+                report.destinations[0].records,
+                report.destinations[0].bytes,
+                report.destinations[1].bytes,
+            )
+        });
+        assert_eq!(report.ok(), Some((4, 3, 7, 2)));
+    }
+}
+
+mod policy {
+    use super::*;
+
+    /// Writes at most 2 bytes at once and fails `failures` times before.
+    struct Flaky {
+        buffer: Vec<u8>,
+        failures: usize,
+        unflushable: bool,
+    }
+
+    impl Flaky {
+        fn new(failures: usize) -> Destination<Self> {
+            Destination::new_with_sink(Self {
+                buffer: Vec::new(),
+                failures,
+                unflushable: false,
+            })
+        }
+
+        /// Buffers every write, but fails to flush them.
+        fn unflushable() -> Destination<Self> {
+            Destination::new_with_sink(Self {
+                buffer: Vec::new(),
+                failures: 0,
+                unflushable: true,
+            })
+        }
+    }
+
+    impl Write for Flaky {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.failures > 0 && self.buffer.len() == 2 {
+                self.failures -= 1;
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+
+            let len = buf.len().min(2);
+            self.buffer.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            if self.unflushable {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            Ok(())
+        }
+    }
+
+    fn buffers(destinations: Vec<Destination<Flaky>>) -> Vec<Vec<u8>> {
+        destinations
+            .into_iter()
+            .map(|destination| destination.into_sink().buffer)
+            .collect()
+    }
+
+    #[test]
+    fn fail_fast() {
+        let mut source = "0\n1\n22\n".as_bytes();
+        let mut destinations = [Flaky::new(1)];
+
+        let report = round_robin_with_policy(&mut source, &mut destinations, ErrorPolicy::FailFast);
+
+        assert_eq!(
+            report.map_err(|error| error.kind()).err(),
+            Some(io::ErrorKind::BrokenPipe)
+        );
+    }
+
+    #[test]
+    fn retry_without_duplicates() {
+        let mut source = "0\n1\n22\n".as_bytes();
+        let mut destinations = vec![Flaky::new(2)];
+
+        let report = round_robin_with_policy(&mut source, &mut destinations, ErrorPolicy::Retry(2));
+
+        assert!(report.is_ok());
+        assert_eq!(buffers(destinations), vec![b"0\n1\n22\n".to_vec()]);
+    }
+
+    #[test]
+    fn exhausted_retries() {
+        let mut source = "0\n1\n".as_bytes();
+        let mut destinations = [Flaky::new(2)];
+
+        let report = round_robin_with_policy(&mut source, &mut destinations, ErrorPolicy::Retry(1));
+
+        assert!(report.is_err());
+    }
+
+    #[test]
+    fn skip_redistributes() {
+        let mut source = "0\n1\n2\n3\n4\n".as_bytes();
+        let mut destinations = vec![Flaky::new(0), Flaky::new(1), Flaky::new(0)];
+
+        let report = round_robin_with_policy(&mut source, &mut destinations, ErrorPolicy::Skip);
+
+        let failures = report.map(|report| report.failures);
+        assert_eq!(
+            failures
+                .map(|failures| (failures[0].index, failures[0].record))
+                .ok(),
+            Some((1, 4))
+        );
+        assert_eq!(
+            buffers(destinations),
+            vec![b"0\n3\n".to_vec(), b"1\n".to_vec(), b"2\n4\n".to_vec()]
+        );
+    }
+
+    #[test]
+    fn skip_redistributes_unflushed_records() {
+        let mut source = "0\n1\n2\n3\n".as_bytes();
+        let mut destinations = vec![Flaky::unflushable(), Flaky::new(0)];
+
+        let report = round_robin_with_policy(&mut source, &mut destinations, ErrorPolicy::Skip);
+
+        let failures =
+            report.map(|report| (report.failures[0].record, report.destinations[0].records));
+        assert_eq!(failures.ok(), Some((0, 0)));
+        assert_eq!(buffers(destinations)[1], b"0\n1\n2\n3\n");
+    }
+
+    #[test]
+    fn skip_keeps_written_records_of_run() {
+        let mut source = "0\n1\n2\n3\n".as_bytes();
+        let mut destinations = vec![
+            Destination {
+                assigned_lines: 3,
+                ..Flaky::new(1)
+            },
+            Flaky::new(0),
+        ];
+
+        let report = round_robin_with_policy(&mut source, &mut destinations, ErrorPolicy::Skip);
+
+        let failures =
+            report.map(|report| (report.failures[0].record, report.destinations[0].records));
+        assert_eq!(failures.ok(), Some((1, 1)));
+        assert_eq!(
+            buffers(destinations),
+            vec![b"0\n".to_vec(), b"1\n2\n3\n".to_vec()]
+        );
+    }
+
+    #[test]
+    fn skip_fails_within_fragmented_record() {
+        let mut source = io::BufReader::with_capacity(1, "01\n2\n".as_bytes());
+        let mut destinations = [Flaky::new(1), Flaky::new(0)];
+
+        let report = round_robin_with_policy(&mut source, &mut destinations, ErrorPolicy::Skip);
+
+        assert_eq!(
+            report
+                .map_err(|error| (error.destination(), error.offset()))
+                .err(),
+            Some((Some(0), 2))
+        );
+    }
+
+    #[test]
+    fn skip_every_destination() {
+        let mut source = "0\n1\n2\n".as_bytes();
+        let mut destinations = [Flaky::new(1), Flaky::new(1)];
+
+        let report = round_robin_with_policy(&mut source, &mut destinations, ErrorPolicy::Skip);
+
+        assert!(report.is_err());
+    }
+}
+
+mod records {
+    use super::*;
+    use crate::error::Operation;
+    use crate::keyed::KeyedDestinations;
+
+    #[test]
+    fn crlf_line_endings() {
+        let mut source = "0\r\n1\n2".as_bytes();
+        let mut destinations = [Destination::buffer()];
+
+        let report = round_robin(&mut source, &mut destinations);
+        let [destination] = destinations;
+
+        assert_eq!(report.map(|report| report.records_written).ok(), Some(3));
+        assert_eq!(
+            destination.into_utf8_string().ok(),
+            Some("0\n1\n2\n".to_owned())
+        );
+    }
+
+    #[test]
+    fn fragments_stay_in_destination() {
+        let mut source = io::BufReader::with_capacity(2, "a123\nb\na4\n".as_bytes());
+        let mut destinations = KeyedDestinations::new();
+
+        let report = by_key(
+            &mut source,
+            &mut destinations,
+            |line| line.get(..1).unwrap_or_default(),
+            |_key| Ok(Vec::new()),
+        );
+
+        assert_eq!(
+            report.map(|report| report.destinations[0].records).ok(),
+            Some(2)
+        );
+        let buffers = destinations
+            .into_inner()
+            .into_iter()
+            .map(|(key, destination)| (key, destination.sink))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            buffers,
+            vec![
+                ("a".to_owned(), b"a123\na4\n".to_vec()),
+                ("b".to_owned(), b"b\n".to_vec())
+            ]
+        );
+    }
+
+    #[test]
+    fn read_error_position() {
+        let mut source = &b"0\r\n\xff\n"[..];
+        let mut destinations = [Destination::buffer()];
+
+        let error = round_robin(&mut source, &mut destinations).err();
+
+        assert_eq!(
+            error.map(|error| (error.operation(), error.record(), error.offset())),
+            Some((Operation::Read, 1, 3))
+        );
+    }
+}
+
+mod checkpoint {
+    use super::*;
+    use crate::checkpoint::Checkpoint;
+    use crate::error::Operation;
+    use std::num::NonZeroU64;
+
+    fn every(records: u64) -> io::Result<NonZeroU64> {
+        NonZeroU64::new(records).ok_or_else(|| io::ErrorKind::InvalidInput.into())
+    }
+
+    #[test]
+    fn resume_in_distribution() -> io::Result<()> {
+        let mut source = "3\n4\n5\n".as_bytes();
+        let mut destinations = vec![Destination::buffer_with_lines(2), Destination::buffer()];
+
+        round_robin_resumable(
+            &mut source,
+            &mut destinations,
+            ErrorPolicy::FailFast,
+            &Checkpoint::new(3, 6, vec![4, 2]),
+            every(1)?,
+            |_| Ok(()),
+        )?;
+
+        let [first, second] = [destinations.remove(0), destinations.remove(0)];
+        assert_eq!(first.into_utf8_string().ok(), Some("3\n4\n".to_owned()));
+        assert_eq!(second.into_utf8_string().ok(), Some("5\n".to_owned()));
+        Ok(())
+    }
+
+    #[test]
+    fn checkpoints_after_interval() -> io::Result<()> {
+        let mut source = "0\n1\n2\n".as_bytes();
+        let mut destinations = [Destination::buffer()];
+        let mut checkpoints = Vec::new();
+
+        round_robin_resumable(
+            &mut source,
+            &mut destinations,
+            ErrorPolicy::FailFast,
+            &Checkpoint::default(),
+            every(2)?,
+            |checkpoint| {
+                checkpoints.push(checkpoint.record);
+                Ok(())
+            },
+        )?;
+
+        assert_eq!(checkpoints, vec![2, 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn checkpoint_transformed_bytes() -> io::Result<()> {
+        let mut source = "0\n1\n".as_bytes();
+        let mut destinations = [Destination::buffer().transform(
+            |record: &crate::transform::Record<'_>, output: &mut String| {
+                output.push_str("record ");
+                output.push_str(record.line());
+            },
+        )];
+        let mut checkpoints = Vec::new();
+
+        round_robin_resumable(
+            &mut source,
+            &mut destinations,
+            ErrorPolicy::FailFast,
+            &Checkpoint::new(0, 0, vec![3]),
+            every(1)?,
+            |checkpoint| {
+                checkpoints.push(checkpoint.destinations[0]);
+                Ok(())
+            },
+        )?;
+
+        assert_eq!(checkpoints, vec![12, 21, 21]);
+        Ok(())
+    }
+
+    #[test]
+    fn failing_checkpoint() -> io::Result<()> {
+        let mut source = "0\n1\n".as_bytes();
+        let mut destinations = [Destination::buffer()];
+
+        let error = round_robin_resumable(
+            &mut source,
+            &mut destinations,
+            ErrorPolicy::FailFast,
+            &Checkpoint::default(),
+            every(1)?,
+            |_| Err(io::ErrorKind::PermissionDenied.into()),
+        )
+        .err();
+
+        assert_eq!(
+            error.map(|error| (error.operation(), error.record())),
+            Some((Operation::Checkpoint, 1))
+        );
+        Ok(())
+    }
+}
+
+mod flush {
+    use super::*;
+    use crate::flush::FlushPolicy;
+    use crate::keyed::KeyedDestinations;
+    use std::num::NonZeroU64;
+
+    /// A sink, which records the count of written bytes at every flush.
+    #[derive(Debug, Default)]
+    struct Flushes {
+        written: usize,
+        flushed_at: Vec<usize>,
+    }
+
+    impl Write for Flushes {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushed_at.push(self.written);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn round_robin_policies() -> io::Result<()> {
+        let mut source = "0\n1\n2\n3\n4\n5\n".as_bytes();
+        let records = NonZeroU64::new(2).ok_or(io::ErrorKind::InvalidInput)?;
+        let mut destinations = [
+            Destination::new_with_sink(Flushes::default()),
+            Destination::new_with_sink(Flushes::default()).flush_policy(FlushPolicy::EveryRecord),
+            Destination::new_with_sink(Flushes::default())
+                .flush_policy(FlushPolicy::Records(records)),
+        ];
+
+        round_robin(&mut source, &mut destinations)?;
+
+        let [at_end, every_record, every_two] = destinations.map(|destination| destination.sink);
+        assert_eq!(at_end.flushed_at, [4]);
+        assert_eq!(every_record.flushed_at, [2, 4, 4]);
+        assert_eq!(every_two.flushed_at, [4, 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn by_key_policy() -> io::Result<()> {
+        let mut source = "a\nb\na\n".as_bytes();
+        let mut destinations = KeyedDestinations::new().flush_policy(FlushPolicy::EveryRecord);
+
+        by_key(
+            &mut source,
+            &mut destinations,
+            |line| line,
+            |_key| Ok(Flushes::default()),
+        )?;
+
+        let flushed_at = destinations
+            .into_inner()
+            .into_iter()
+            .map(|(_, destination)| destination.sink.flushed_at)
+            .collect::<Vec<_>>();
+        assert_eq!(flushed_at, [vec![2, 4, 4], vec![2, 2]]);
+        Ok(())
+    }
+}
+
+mod contiguous {
+    use super::*;
+    use crate::split::contiguous::*;
+    use std::io::Cursor;
+
+    fn split(data: &str, assigned_lines: &[usize]) -> Vec<Option<String>> {
+        let mut destinations = assigned_lines
+            .iter()
+            .map(|lines| Destination::buffer_with_lines(*lines))
+            .collect::<Vec<_>>();
+
+        let report = contiguous(|| Ok(Cursor::new(data)), &mut destinations);
+
+        assert_eq!(
+            report.map(|report| report.bytes_written()).ok(),
+            u64::try_from(data.len()).ok()
+        );
+        destinations
+            .into_iter()
+            .map(|destination| destination.into_utf8_string().ok())
+            .collect()
+    }
+
+    #[test]
+    fn bounds_proportional_to_assigned_lines() {
+        let destinations = [
+            Destination::buffer_with_lines(1),
+            Destination::buffer_with_lines(0),
+            Destination::buffer_with_lines(3),
+        ];
+
+        assert_eq!(bounds(&destinations, 100), vec![0, 25, 25, 100]);
+    }
+
+    #[test]
+    fn record_start_after_offset() -> io::Result<()> {
+        let mut source = Cursor::new("00\n11\n22\n");
+
+        let starts = [0, 1, 3, 4, 9]
+            .into_iter()
+            .map(|offset| record_start(&mut source, offset))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        assert_eq!(starts, vec![0, 3, 3, 6, 9]);
+        Ok(())
+    }
+
+    #[test]
+    fn chunks_keep_records() {
+        assert_eq!(
+            split("0\n11\n222\n3333\n", &[1, 1]),
+            vec![Some("0\n11\n222\n".to_owned()), Some("3333\n".to_owned())]
+        );
+    }
+
+    #[test]
+    fn long_record_spans_chunks() {
+        assert_eq!(
+            split("0123456789\n0\n", &[1, 1, 1]),
+            vec![
+                Some("0123456789\n".to_owned()),
+                Some(String::new()),
+                Some("0\n".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn report_of_chunks() -> io::Result<()> {
+        let mut destinations = [Destination::buffer(), Destination::buffer()];
+
+        let report = contiguous(|| Ok(Cursor::new("0\n1\n2\n3\n")), &mut destinations)?;
+
+        let records = report
+            .destinations
+            .iter()
+            .map(|destination| destination.records)
+            .collect::<Vec<_>>();
+        assert_eq!((report.records_written, records), (4, vec![2, 2]));
+        Ok(())
+    }
+
+    #[test]
+    fn write_error_in_chunk() {
+        let mut buffer = [0_u8; 3];
+        let mut destinations = [
+            Destination::new_with_sink(&mut buffer[..]),
+            Destination::new_with_sink(&mut [0_u8; 0][..]),
+        ];
+
+        let error = contiguous(|| Ok(Cursor::new("0\n1\n2\n3\n")), &mut destinations).err();
+
+        assert_eq!(
+            error.map(|error| (error.destination(), error.record(), error.offset())),
+            Some((Some(0), 0, 0))
+        );
+    }
 }
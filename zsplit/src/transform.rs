@@ -0,0 +1,195 @@
+//! Transformation of the records written to a destination.
+
+use crate::error::Position;
+use crate::records::Run;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+#[cfg(test)]
+#[path = "./transform_test.rs"]
+mod transform_test;
+
+/// A record of the source passed to a [`Transform`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record<'a> {
+    line: &'a str,
+    number: u64,
+    offset: u64,
+}
+
+impl<'a> Record<'a> {
+    /// Creates a [`Record`].
+    #[must_use]
+    #[inline]
+    pub fn new(line: &'a str, number: u64, offset: u64) -> Self {
+        Self {
+            line,
+            number,
+            offset,
+        }
+    }
+
+    /// The record without its line ending.
+    #[must_use]
+    #[inline]
+    pub fn line(&self) -> &'a str {
+        self.line
+    }
+
+    /// The number of the record in the source, starting at 0.
+    #[must_use]
+    #[inline]
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+
+    /// The byte offset of the start of the record in the source.
+    #[must_use]
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+/// Reshapes the records before they are written to a destination.
+///
+/// It's implemented for every closure taking a [`Record`] and the output.
+pub trait Transform: Send + Sync {
+    /// Writes the transformed `record` into the empty `output`.
+    ///
+    /// The line ending is appended afterwards.
+    fn transform(&self, record: &Record<'_>, output: &mut String);
+}
+
+impl<F> Transform for F
+where
+    F: Fn(&Record<'_>, &mut String) + Send + Sync,
+{
+    #[inline]
+    fn transform(&self, record: &Record<'_>, output: &mut String) {
+        self(record, output);
+    }
+}
+
+/// The progress of writing a run of records to a destination.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Written {
+    /// The bytes of the run, which were written, so a repetition doesn't write any byte twice.
+    pub(crate) source: usize,
+
+    /// The bytes written to the sink for the completely written records of the run.
+    pub(crate) sink: usize,
+}
+
+/// The [`Transform`] of a destination with the state of the record, which is written.
+#[derive(Clone)]
+pub struct Transformer {
+    transform: Arc<dyn Transform>,
+    /// The fragments of a record exceeding the buffer of the source.
+    fragments: String,
+    /// The position of the first fragment.
+    start: Position,
+    /// The transformed record and its length, which is already written.
+    transformed: String,
+    written: usize,
+}
+
+impl Transformer {
+    /// Creates a [`Transformer`] applying `transform` to every record.
+    #[inline]
+    pub fn new<T: Transform + 'static>(transform: T) -> Self {
+        Self {
+            transform: Arc::new(transform),
+            fragments: String::new(),
+            start: Position::default(),
+            transformed: String::new(),
+            written: 0,
+        }
+    }
+
+    /// Writes the transformed records of the `run` after its first `written` bytes to the `sink`,
+    /// so a repetition doesn't write any byte twice.
+    ///
+    /// `written` counts only completely written records.
+    pub(crate) fn write<W: Write>(
+        &mut self,
+        sink: &mut W,
+        run: &Run<'_>,
+        written: &mut Written,
+    ) -> io::Result<()> {
+        let mut rest = *run;
+        rest.take_within(written.source);
+
+        while !rest.is_empty() {
+            let record = rest.take(1);
+            if record.is_fragment() {
+                self.collect(&record);
+            } else {
+                written.sink += self.write_record(sink, &record)?;
+            }
+            written.source += record.as_str().len();
+        }
+        Ok(())
+    }
+
+    /// Keeps the `fragment` of a record until the record is complete.
+    fn collect(&mut self, fragment: &Run<'_>) {
+        if self.fragments.is_empty() {
+            self.start = fragment.position();
+        }
+        self.fragments.push_str(fragment.as_str());
+    }
+
+    /// Writes the transformed `record` and returns its length.
+    fn write_record<W: Write>(&mut self, sink: &mut W, record: &Run<'_>) -> io::Result<usize> {
+        if self.transformed.is_empty() {
+            let line = record.as_str();
+            let line = line.strip_suffix('\n').unwrap_or(line);
+
+            let record = if self.fragments.is_empty() {
+                Record::new(line, record.position().record, record.position().offset)
+            } else {
+                self.fragments.push_str(line);
+                Record::new(&self.fragments, self.start.record, self.start.offset)
+            };
+            self.transform.transform(&record, &mut self.transformed);
+            self.transformed.push('\n');
+        }
+
+        write_remainder(sink, self.transformed.as_bytes(), &mut self.written)?;
+
+        let len = self.transformed.len();
+        self.transformed.clear();
+        self.fragments.clear();
+        self.written = 0;
+        Ok(len)
+    }
+}
+
+impl fmt::Debug for Transformer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transformer")
+            .field("fragments", &self.fragments.len())
+            .field("transformed", &self.transformed)
+            .field("written", &self.written)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Writes the part of `buf` after `written`, so a repetition doesn't write any byte twice.
+pub(crate) fn write_remainder<W: Write>(
+    sink: &mut W,
+    buf: &[u8],
+    written: &mut usize,
+) -> io::Result<()> {
+    while *written < buf.len() {
+        match sink.write(&buf[*written..]) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(len) => *written += len,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(())
+}
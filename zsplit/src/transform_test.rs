@@ -0,0 +1,96 @@
+use super::*;
+use crate::records::for_each_run;
+
+/// Writes at most 2 bytes at once and fails every other write.
+#[derive(Default)]
+struct Flaky {
+    buffer: Vec<u8>,
+    fail: bool,
+}
+
+impl Write for Flaky {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.fail = !self.fail;
+        if self.fail {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+
+        let len = buf.len().min(2);
+        self.buffer.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Transforms the records of the `source` read with a buffer of `capacity` bytes into `sink`,
+/// repeating every failed write.
+fn transform<W: Write>(source: &str, capacity: usize, sink: &mut W) -> crate::Result<()> {
+    let mut transformer = Transformer::new(|record: &Record<'_>, output: &mut String| {
+        output.push_str(&record.number().to_string());
+        output.push('@');
+        output.push_str(&record.offset().to_string());
+        output.push(':');
+        output.push_str(record.line());
+    });
+    let mut source = io::BufReader::with_capacity(capacity, source.as_bytes());
+
+    for_each_run(&mut source, Position::default(), |run| {
+        let mut written = Written::default();
+        while transformer.write(sink, run, &mut written).is_err() {}
+        run.take(u64::MAX);
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[test]
+fn transform_records() {
+    let mut sink = Vec::new();
+
+    let result = transform("a\r\nb\nc", 64, &mut sink);
+
+    assert!(result.is_ok());
+    assert_eq!(sink, b"0@0:a\n1@3:b\n2@5:c\n");
+}
+
+#[test]
+fn repeated_writes_without_duplicates() {
+    let mut sink = Flaky::default();
+
+    let result = transform("a\nb\n", 64, &mut sink);
+
+    assert!(result.is_ok());
+    assert_eq!(sink.buffer, b"0@0:a\n1@2:b\n");
+}
+
+#[test]
+fn transform_complete_record_of_fragments() {
+    let mut sink = Vec::new();
+
+    let result = transform("0123456\n7\n", 2, &mut sink);
+
+    assert!(result.is_ok());
+    assert_eq!(sink, b"0@0:0123456\n1@8:7\n");
+}
+
+#[test]
+fn count_transformed_bytes() {
+    let mut transformer = Transformer::new(|record: &Record<'_>, output: &mut String| {
+        output.push_str(record.line());
+        output.push_str(record.line());
+    });
+    let mut sink = Vec::new();
+    let mut written = Written::default();
+
+    let result = for_each_run(&mut "ab\nc\n".as_bytes(), Position::default(), |run| {
+        let result = transformer.write(&mut sink, run, &mut written);
+        run.take(u64::MAX);
+        result.map_err(|error| crate::Error::write(run.position(), Some(0), error))
+    });
+
+    assert!(result.is_ok());
+    assert_eq!((written.source, written.sink), (5, 8));
+}